@@ -0,0 +1,116 @@
+//! Measures the throughput and allocations of decoding `state_changed` events
+//!
+//! Run with `cargo bench --bench event_decoding`.
+use r_hassclient::{HaEventData, StateChangedEvent, WsEvent};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const EVENTS: usize = 50_000;
+// The number of callbacks reading each event
+const LISTENERS: usize = 3;
+
+const STATE_CHANGED: &str = r#"{
+  "id": 1,
+  "type": "event",
+  "event": {
+    "event_type": "state_changed",
+    "data": {
+      "entity_id": "light.kitchen",
+      "old_state": {
+        "entity_id": "light.kitchen",
+        "state": "off",
+        "attributes": {"friendly_name": "Kitchen", "supported_color_modes": ["color_temp", "hs"]},
+        "last_changed": "2023-08-28T09:08:13.985677+00:00",
+        "last_updated": "2023-08-28T09:08:13.985677+00:00",
+        "context": {"id": "01H8XPD611JJ7Q3WP5VT5FVEWN", "parent_id": null, "user_id": null}
+      },
+      "new_state": {
+        "entity_id": "light.kitchen",
+        "state": "on",
+        "attributes": {"friendly_name": "Kitchen", "brightness": 180, "color_temp_kelvin": 2700},
+        "last_changed": "2023-08-28T09:08:17.985677+00:00",
+        "last_updated": "2023-08-28T09:08:17.985677+00:00",
+        "context": {"id": "01H8XPDA5V3XW0JH9D2RDM6Z1K", "parent_id": null, "user_id": null}
+      }
+    },
+    "origin": "LOCAL",
+    "time_fired": "2023-08-28T09:08:17.985677+00:00",
+    "context": {"id": "01H8XPDA5V3XW0JH9D2RDM6Z1K", "parent_id": null, "user_id": null}
+  }
+}"#;
+
+fn measure(name: &str, decode: impl Fn(&str)) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..EVENTS {
+        decode(black_box(STATE_CHANGED));
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    println!(
+        "{:<32} {:>10.0} events/s {:>8.1} allocations/event",
+        name,
+        EVENTS as f64 / elapsed.as_secs_f64(),
+        allocations as f64 / EVENTS as f64
+    );
+}
+
+fn main() {
+    println!("{} events, {} listeners per event", EVENTS, LISTENERS);
+
+    // The data is parsed into a Value and cloned for every listener
+    measure("value, decoded per listener", |json| {
+        let message: Value = serde_json::from_str(json).unwrap();
+        for _ in 0..LISTENERS {
+            let data = message["event"]["data"].clone();
+            black_box(StateChangedEvent::deserialize(data).unwrap());
+        }
+    });
+
+    // The data is kept raw and decoded by the first listener reading it
+    measure("raw, decoded once", |json| {
+        let event: WsEvent = serde_json::from_str(json).unwrap();
+        for _ in 0..LISTENERS {
+            let event = event.clone();
+            match event.event.event_data() {
+                Ok(HaEventData::StateChangedEvent(data)) => {
+                    black_box(data);
+                }
+                Err(err) => panic!("{}", err),
+            }
+        }
+    });
+
+    // The listeners only look at the event type, the data is never decoded
+    measure("raw, not decoded", |json| {
+        let event: WsEvent = serde_json::from_str(json).unwrap();
+        for _ in 0..LISTENERS {
+            black_box(event.clone().event.event_type);
+        }
+    });
+}
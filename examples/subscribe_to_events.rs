@@ -1,28 +1,23 @@
-use std::env::var;
-
 // use std::time::Duration;
-use lazy_static::lazy_static;
-use r_hassclient::{client::HaClient, HaEventData, WsEvent};
+use r_hassclient::{auth::EnvToken, client::HaClient, HaEventData, WsEvent};
 // use r_hassclient::HaConnection;
 // use serde_json::json;
 use tokio::signal;
 // use tokio::time::sleep;
-lazy_static! {
-    static ref TOKEN: String =
-        var("HASS_TOKEN").expect("please set up the HASS_TOKEN env variable before running this");
-}
 #[tokio::main]
 async fn main() {
     let addr = "ws://localhost:8124/api/websocket";
     let addr = url::Url::parse(addr).unwrap();
 
-    let mut client = HaClient::builder().build();
+    let mut client = HaClient::builder()
+        .token_source(EnvToken::new("HASS_TOKEN"))
+        .build();
     let mut conn = client
         .connect_async(addr)
         .await
         .expect("Error connecting to Home Assistant!");
 
-    if let Err(err) = conn.authenticate_with_token(&TOKEN).await {
+    if let Err(err) = conn.authenticate().await {
         println!("Failed to login to Home Assistant, {}", err);
         return;
     }
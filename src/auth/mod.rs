@@ -0,0 +1,36 @@
+//! Access tokens for authenticating with Home Assistant
+//!
+//! The connection gets its token from a [`TokenSource`], like a string, an
+//! environment variable or a mounted secret file. With the `oauth` feature,
+//! [`TokenProvider`] logs in with the auth api of Home Assistant and refreshes
+//! the short-lived access tokens.
+//!
+//! ```no_run
+//! # async fn example() -> r_hassclient::HassResult<()> {
+//! use r_hassclient::{auth::FileToken, HaClient};
+//!
+//! let url = url::Url::parse("ws://localhost:8123/api/websocket")?;
+//! let mut conn = HaClient::builder()
+//!     .token_source(FileToken::new("/var/run/secrets/hass/token"))
+//!     .build()
+//!     .connect_async(url)
+//!     .await?;
+//! conn.authenticate().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod secret;
+pub use secret::Secret;
+pub(crate) use secret::REDACTED;
+
+mod source;
+pub use source::{CallbackToken, EnvToken, FileToken, StaticToken, SupervisorToken, TokenSource};
+
+#[cfg(feature = "oauth")]
+mod oauth;
+#[cfg(feature = "oauth")]
+pub use oauth::TokenProvider;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,254 @@
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use url::Url;
+
+use super::{Secret, TokenSource};
+use crate::{config, HassError, HassResult};
+
+/// Access tokens are refreshed when they expire within this margin
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Logs in with the auth api of Home Assistant and keeps the access token fresh
+///
+/// The provider runs the `login_flow` with a username and password, or
+/// exchanges an authorization code, and stores the access and refresh tokens.
+/// As a [`TokenSource`] of the connection, the access token is refreshed
+/// shortly before it expires, and after Home Assistant rejected it.
+///
+/// ```no_run
+/// # async fn example() -> r_hassclient::HassResult<()> {
+/// use r_hassclient::{auth::TokenProvider, HaClient};
+///
+/// let base_url = url::Url::parse("http://localhost:8123")?;
+/// let provider = TokenProvider::new(base_url, "http://my-app.local");
+/// provider.login("user", "password").await?;
+///
+/// let url = url::Url::parse("ws://localhost:8123/api/websocket")?;
+/// let mut conn = HaClient::builder().build().connect_async(url).await?;
+/// conn.authenticate_with(&provider).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TokenProvider {
+    http: reqwest::Client,
+    base_url: Url,
+    client_id: String,
+    tokens: Mutex<Tokens>,
+}
+
+#[derive(Default)]
+struct Tokens {
+    access_token: Option<Secret>,
+    refresh_token: Option<Secret>,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Secret,
+    expires_in: u64,
+    // only returned when exchanging an authorization code
+    refresh_token: Option<Secret>,
+}
+
+#[derive(Deserialize)]
+struct FlowResponse {
+    #[serde(rename = "type")]
+    flow_type: String,
+    flow_id: Option<String>,
+    result: Option<Value>,
+    errors: Option<Value>,
+}
+
+impl TokenProvider {
+    /// Creates a provider for Home Assistant at the base url, like `http://localhost:8123`
+    ///
+    /// The client id is the url identifying the application to Home Assistant.
+    /// A base url with a path, like `https://example.com/ha`, keeps the path.
+    pub fn new(base_url: Url, client_id: &str) -> TokenProvider {
+        TokenProvider {
+            http: reqwest::Client::new(),
+            base_url: config::rest_url(&base_url),
+            client_id: client_id.to_owned(),
+            tokens: Mutex::new(Tokens::default()),
+        }
+    }
+
+    /// Starts from a refresh token stored by an earlier login
+    pub fn with_refresh_token(self, refresh_token: &str) -> TokenProvider {
+        TokenProvider {
+            tokens: Mutex::new(Tokens {
+                refresh_token: Some(Secret::from(refresh_token)),
+                ..Tokens::default()
+            }),
+            ..self
+        }
+    }
+
+    /// Logs in with the username and password of a Home Assistant user
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if Home Assistant rejects the credentials.
+    pub async fn login(&self, username: &str, password: &str) -> HassResult<()> {
+        let flow: FlowResponse = self
+            .post_json(
+                "auth/login_flow",
+                json!({
+                    "client_id": self.client_id,
+                    "handler": ["homeassistant", null],
+                    "redirect_uri": self.client_id,
+                }),
+            )
+            .await?;
+        let flow_id = flow
+            .flow_id
+            .ok_or_else(|| HassError::AuthenticationFailed("no login flow started".to_owned()))?;
+
+        let flow: FlowResponse = self
+            .post_json(
+                &format!("auth/login_flow/{}", flow_id),
+                json!({
+                    "client_id": self.client_id,
+                    "username": username,
+                    "password": password,
+                }),
+            )
+            .await?;
+        match (flow.flow_type.as_str(), flow.result) {
+            ("create_entry", Some(Value::String(code))) => self.exchange_code(&code).await,
+            _ => Err(HassError::AuthenticationFailed(match flow.errors {
+                Some(errors) => errors.to_string(),
+                None => format!("login flow ended with {}", flow.flow_type),
+            })),
+        }
+    }
+
+    /// Exchanges an authorization code for an access and a refresh token
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if Home Assistant rejects the code.
+    pub async fn exchange_code(&self, code: &str) -> HassResult<()> {
+        let mut tokens = self.tokens.lock().await;
+        let response = self
+            .post_token(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("client_id", &self.client_id),
+            ])
+            .await?;
+        tokens.store(response);
+        Ok(())
+    }
+
+    /// The access token, refreshed first when it is about to expire
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the token can not be refreshed.
+    pub async fn access_token(&self) -> HassResult<Secret> {
+        let mut tokens = self.tokens.lock().await;
+        let expiring = tokens
+            .expires_at
+            .is_none_or(|expires_at| Instant::now() + REFRESH_MARGIN >= expires_at);
+        if tokens.access_token.is_none() || expiring {
+            self.refresh_locked(&mut tokens).await?;
+        }
+        tokens
+            .access_token
+            .clone()
+            .ok_or_else(|| HassError::AuthenticationFailed("no access token".to_owned()))
+    }
+
+    /// Gets a new access token with the refresh token
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no refresh token or
+    /// Home Assistant rejects it.
+    pub async fn refresh(&self) -> HassResult<()> {
+        let mut tokens = self.tokens.lock().await;
+        self.refresh_locked(&mut tokens).await
+    }
+
+    /// The refresh token, to store for the next start
+    pub async fn refresh_token(&self) -> Option<Secret> {
+        self.tokens.lock().await.refresh_token.clone()
+    }
+
+    /// Drops the access token after Home Assistant rejected it
+    async fn invalidate(&self) {
+        let mut tokens = self.tokens.lock().await;
+        tokens.access_token = None;
+        tokens.expires_at = None;
+    }
+
+    async fn refresh_locked(&self, tokens: &mut Tokens) -> HassResult<()> {
+        let refresh_token = tokens.refresh_token.clone().ok_or_else(|| {
+            HassError::AuthenticationFailed("not logged in, no refresh token".to_owned())
+        })?;
+        let response = self
+            .post_token(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token.expose()),
+                ("client_id", &self.client_id),
+            ])
+            .await?;
+        tokens.store(response);
+        Ok(())
+    }
+
+    async fn post_token(&self, form: &[(&str, &str)]) -> HassResult<TokenResponse> {
+        let url = self.base_url.join("auth/token")?;
+        let response = self.http.post(url).form(form).send().await;
+        parse_response(response).await
+    }
+
+    async fn post_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        body: Value,
+    ) -> HassResult<T> {
+        let url = self.base_url.join(path)?;
+        let response = self.http.post(url).json(&body).send().await;
+        parse_response(response).await
+    }
+}
+
+impl TokenSource for TokenProvider {
+    fn token(&self) -> BoxFuture<'_, HassResult<Secret>> {
+        Box::pin(self.access_token())
+    }
+
+    fn rejected(&self) -> BoxFuture<'_, ()> {
+        Box::pin(self.invalidate())
+    }
+}
+
+impl Tokens {
+    fn store(&mut self, response: TokenResponse) {
+        self.access_token = Some(response.access_token);
+        self.expires_at = Some(Instant::now() + Duration::from_secs(response.expires_in));
+        if let Some(refresh_token) = response.refresh_token {
+            self.refresh_token = Some(refresh_token);
+        }
+    }
+}
+
+async fn parse_response<T: serde::de::DeserializeOwned>(
+    response: reqwest::Result<reqwest::Response>,
+) -> HassResult<T> {
+    let response = response
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|err| HassError::AuthenticationFailed(err.to_string()))?;
+    response
+        .json()
+        .await
+        .map_err(|err| HassError::AuthenticationFailed(err.to_string()))
+}
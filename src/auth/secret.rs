@@ -0,0 +1,69 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// Written instead of a secret in logs and recordings
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// A token that does not leak into logs
+///
+/// `Debug` and `Display` print `<redacted>` and the memory is zeroed when the
+/// secret is dropped. Use [`Secret::expose`] where the token itself is needed,
+/// serializing a secret writes the token, like in the `auth` message.
+///
+/// ```
+/// use r_hassclient::auth::Secret;
+///
+/// let token = Secret::from("my-token");
+/// assert_eq!(format!("{:?}", token), "Secret(<redacted>)");
+/// assert_eq!(token.expose(), "my-token");
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(Zeroizing<String>);
+
+impl Secret {
+    pub fn new(secret: String) -> Secret {
+        Secret(Zeroizing::new(secret))
+    }
+
+    /// The token itself, keep it out of logs
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Secret::new(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Secret::new(secret.to_owned())
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.expose())
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Secret::new)
+    }
+}
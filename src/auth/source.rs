@@ -0,0 +1,163 @@
+use futures_util::future::BoxFuture;
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tokio::sync::Mutex;
+use zeroize::Zeroizing;
+
+use super::Secret;
+use crate::{HassError, HassResult};
+
+/// Where the connection gets the access token from when it authenticates
+///
+/// Implemented by [`StaticToken`], [`EnvToken`], [`FileToken`],
+/// [`SupervisorToken`] and [`CallbackToken`].
+pub trait TokenSource: Send + Sync {
+    /// The access token to authenticate with
+    fn token(&self) -> BoxFuture<'_, HassResult<Secret>>;
+
+    /// Called when Home Assistant rejected the token, before the next authentication
+    fn rejected(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
+}
+
+/// A token given as a string, like a long-lived access token
+pub struct StaticToken(Secret);
+
+impl StaticToken {
+    pub fn new(token: &str) -> StaticToken {
+        StaticToken(Secret::from(token))
+    }
+}
+
+impl TokenSource for StaticToken {
+    fn token(&self) -> BoxFuture<'_, HassResult<Secret>> {
+        Box::pin(async { Ok(self.0.clone()) })
+    }
+}
+
+/// A token read from an environment variable on every authentication
+pub struct EnvToken {
+    name: String,
+}
+
+impl EnvToken {
+    pub fn new(name: &str) -> EnvToken {
+        EnvToken {
+            name: name.to_owned(),
+        }
+    }
+}
+
+impl TokenSource for EnvToken {
+    fn token(&self) -> BoxFuture<'_, HassResult<Secret>> {
+        Box::pin(async {
+            std::env::var(&self.name).map(Secret::new).map_err(|_| {
+                HassError::AuthenticationFailed(format!(
+                    "the environment variable {} is not set",
+                    self.name
+                ))
+            })
+        })
+    }
+}
+
+/// The token of a Home Assistant add-on, from the `SUPERVISOR_TOKEN` variable
+///
+/// Add-ons connect to `ws://supervisor/core/websocket` with this token.
+pub struct SupervisorToken(EnvToken);
+
+impl SupervisorToken {
+    pub fn new() -> SupervisorToken {
+        SupervisorToken(EnvToken::new("SUPERVISOR_TOKEN"))
+    }
+}
+
+impl Default for SupervisorToken {
+    fn default() -> Self {
+        SupervisorToken::new()
+    }
+}
+
+impl TokenSource for SupervisorToken {
+    fn token(&self) -> BoxFuture<'_, HassResult<Secret>> {
+        self.0.token()
+    }
+}
+
+/// A token read from a file, like a mounted Kubernetes secret
+///
+/// The file is read again when it was modified since the last read, so a
+/// rotated secret is used on the next authentication. Surrounding whitespace
+/// is trimmed.
+pub struct FileToken {
+    path: PathBuf,
+    cached: Mutex<Option<(SystemTime, Secret)>>,
+}
+
+impl FileToken {
+    pub fn new(path: impl AsRef<Path>) -> FileToken {
+        FileToken {
+            path: path.as_ref().to_owned(),
+            cached: Mutex::new(None),
+        }
+    }
+
+    async fn read(&self) -> HassResult<Secret> {
+        let error = |err: std::io::Error| {
+            HassError::AuthenticationFailed(format!(
+                "can not read the token from {}: {}",
+                self.path.display(),
+                err
+            ))
+        };
+        let modified = tokio::fs::metadata(&self.path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .map_err(error)?;
+        let mut cached = self.cached.lock().await;
+        if let Some((read_at, token)) = cached.as_ref() {
+            if *read_at == modified {
+                return Ok(token.clone());
+            }
+        }
+        let contents = Zeroizing::new(tokio::fs::read_to_string(&self.path).await.map_err(error)?);
+        let token = Secret::from(contents.trim());
+        *cached = Some((modified, token.clone()));
+        Ok(token)
+    }
+}
+
+impl TokenSource for FileToken {
+    fn token(&self) -> BoxFuture<'_, HassResult<Secret>> {
+        Box::pin(self.read())
+    }
+}
+
+/// A token returned by a callback, like a lookup in a secret store
+pub struct CallbackToken<F> {
+    callback: F,
+}
+
+impl<F, Fut> CallbackToken<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = HassResult<Secret>> + Send + 'static,
+{
+    pub fn new(callback: F) -> CallbackToken<F> {
+        CallbackToken { callback }
+    }
+}
+
+impl<F, Fut> TokenSource for CallbackToken<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = HassResult<Secret>> + Send + 'static,
+{
+    fn token(&self) -> BoxFuture<'_, HassResult<Secret>> {
+        Box::pin((self.callback)())
+    }
+}
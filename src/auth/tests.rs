@@ -0,0 +1,82 @@
+use std::{
+    fs::File,
+    time::{Duration, SystemTime},
+};
+
+use super::*;
+use crate::{Auth, HaCommand};
+
+#[tokio::test]
+async fn static_token_should_return_the_token() {
+    let source = StaticToken::new("token");
+
+    assert_eq!(source.token().await.unwrap().expose(), "token");
+}
+
+#[tokio::test]
+async fn env_token_should_read_the_variable_on_every_call() {
+    let source = EnvToken::new("R_HASSCLIENT_TEST_TOKEN");
+    assert!(source.token().await.is_err());
+
+    std::env::set_var("R_HASSCLIENT_TEST_TOKEN", "first");
+    assert_eq!(source.token().await.unwrap().expose(), "first");
+    std::env::set_var("R_HASSCLIENT_TEST_TOKEN", "second");
+    assert_eq!(source.token().await.unwrap().expose(), "second");
+}
+
+#[tokio::test]
+async fn file_token_should_reread_the_file_when_modified() {
+    let dir = std::env::temp_dir().join(format!("r-hassclient-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("token");
+    let modified = SystemTime::now();
+    std::fs::write(&path, "first\n").unwrap();
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    let source = FileToken::new(&path);
+    assert_eq!(source.token().await.unwrap().expose(), "first");
+
+    // Rotating the secret changes the modification time
+    std::fs::write(&path, "second\n").unwrap();
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified + Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(source.token().await.unwrap().expose(), "second");
+}
+
+#[tokio::test]
+async fn callback_token_should_call_the_callback() {
+    let source = CallbackToken::new(|| async { Ok(Secret::from("from callback")) });
+
+    assert_eq!(source.token().await.unwrap().expose(), "from callback");
+}
+
+#[test]
+fn secret_should_be_redacted_in_debug_and_display() {
+    let secret = Secret::from("token");
+
+    assert_eq!(format!("{:?}", secret), "Secret(<redacted>)");
+    assert_eq!(secret.to_string(), "<redacted>");
+    assert_eq!(secret.expose(), "token");
+}
+
+#[test]
+fn auth_command_should_only_expose_token_when_sent() {
+    let command = HaCommand::AuthInfo(Auth {
+        msg_type: "auth".to_owned(),
+        access_token: Secret::from("token"),
+    });
+
+    assert!(format!("{:?}", command).contains("access_token: Secret(<redacted>)"));
+    assert_eq!(
+        command.to_tungstenite_message().to_string(),
+        r#"{"type":"auth","access_token":"token"}"#
+    );
+}
@@ -0,0 +1,105 @@
+use std::env::{args, var};
+use std::process::exit;
+
+use r_hassclient::{
+    codegen::{generate, Snapshot},
+    HaClient, HassResult,
+};
+
+const USAGE: &str = "\
+Generates a Rust module with typed entities and services from Home Assistant
+
+Usage:
+    hass-codegen --url <ws-url> --out <file> [--save-snapshot <file>]
+    hass-codegen --snapshot <file> --out <file>
+
+Options:
+    --url <ws-url>            Websocket url, i.e. ws://localhost:8123/api/websocket.
+                              The access token is read from the HASS_TOKEN env variable
+    --snapshot <file>         Generate from a saved JSON snapshot instead of a live instance
+    --save-snapshot <file>    Save the fetched snapshot for offline builds
+    --out <file>              The Rust file to generate";
+
+#[derive(Default)]
+struct Args {
+    url: Option<String>,
+    snapshot: Option<String>,
+    save_snapshot: Option<String>,
+    out: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--url" => &mut parsed.url,
+            "--snapshot" => &mut parsed.snapshot,
+            "--save-snapshot" => &mut parsed.save_snapshot,
+            "--out" => &mut parsed.out,
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            _ => return Err(format!("unknown argument {}\n\n{}", arg, USAGE)),
+        };
+        *value = Some(
+            args.next()
+                .ok_or_else(|| format!("missing value for {}\n\n{}", arg, USAGE))?,
+        );
+    }
+    if parsed.out.is_none() || parsed.url.is_some() == parsed.snapshot.is_some() {
+        return Err(USAGE.to_owned());
+    }
+    Ok(parsed)
+}
+
+async fn fetch_snapshot(url: &str) -> HassResult<Snapshot> {
+    let token = var("HASS_TOKEN").map_err(|_| {
+        r_hassclient::HassError::AuthenticationFailed("HASS_TOKEN is not set".to_owned())
+    })?;
+    let mut client = HaClient::builder().build();
+    let mut conn = client.connect_async(url::Url::parse(url)?).await?;
+    conn.authenticate_with_token(&token).await?;
+    Snapshot::fetch(&mut conn).await
+}
+
+#[tokio::main]
+async fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(usage) => {
+            eprintln!("{}", usage);
+            exit(2);
+        }
+    };
+
+    let snapshot = match (&args.url, &args.snapshot) {
+        (Some(url), _) => fetch_snapshot(url).await,
+        (_, Some(path)) => Snapshot::load(path),
+        _ => unreachable!("validated by parse_args"),
+    };
+    let snapshot = match snapshot {
+        Ok(snapshot) => snapshot,
+        Err(err) => {
+            eprintln!("Failed to get the Home Assistant snapshot: {}", err);
+            exit(1);
+        }
+    };
+
+    if let Some(path) = &args.save_snapshot {
+        if let Err(err) = snapshot.save(path) {
+            eprintln!("Failed to save the snapshot: {}", err);
+            exit(1);
+        }
+    }
+
+    let out = args.out.expect("validated by parse_args");
+    if let Err(err) = std::fs::write(&out, generate(&snapshot)) {
+        eprintln!("Failed to write {}: {}", out, err);
+        exit(1);
+    }
+    println!(
+        "Generated {} with {} entities and {} service domains",
+        out,
+        snapshot.states.len(),
+        snapshot.services.len()
+    );
+}
@@ -0,0 +1,22 @@
+use serde_json::value::RawValue;
+use tokio::sync::mpsc;
+
+use super::{dispatch_message, HaListener};
+
+#[tokio::test]
+async fn response_after_dropped_connection_should_be_ignored() {
+    let (to_client, from_ha) = mpsc::channel(1);
+    drop(from_ha);
+    let listeners = HaListener::default();
+    let message =
+        RawValue::from_string(r#"{"id": 1, "type": "result", "success": true}"#.to_owned())
+            .unwrap();
+
+    dispatch_message(&message, &to_client, &listeners).await;
+    dispatch_message(
+        &RawValue::from_string("[]".to_owned()).unwrap(),
+        &to_client,
+        &listeners,
+    )
+    .await;
+}
@@ -0,0 +1,449 @@
+//! Generates a Rust module with typed entities and services from a Home Assistant instance
+//!
+//! The generated module has one constant per entity and one builder per service,
+//! so a renamed or removed entity or service becomes a compile error. The input
+//! is a [`Snapshot`] that is either fetched from a live instance or loaded from
+//! a saved JSON file, for offline builds.
+//!
+//! ```no_run
+//! # async fn example(conn: &mut r_hassclient::client::HaConnection) -> r_hassclient::HassResult<()> {
+//! use r_hassclient::codegen::{generate, Snapshot};
+//!
+//! let snapshot = Snapshot::fetch(conn).await?;
+//! snapshot.save("ha_snapshot.json")?;
+//! std::fs::write("src/ha.rs", generate(&snapshot)).unwrap();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The `hass-codegen` binary does the same from the command line.
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    client::HaConnection, services::Target, HaServices, HaState, HassResult, ServiceDescription,
+    ServiceField,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Re-exported for the generated code, so it does not depend on `serde_json` directly
+pub use serde_json::Value;
+
+/// The states and services of a Home Assistant instance at one point in time
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Snapshot {
+    pub states: Vec<HaState>,
+    pub services: HaServices,
+}
+
+impl Snapshot {
+    /// Fetches the current states and services from Home Assistant
+    pub async fn fetch(conn: &mut HaConnection) -> HassResult<Snapshot> {
+        let states = conn.get_states().await?;
+        let services = conn.get_services().await?;
+        Ok(Snapshot { states, services })
+    }
+
+    /// Loads a snapshot previously saved with [`Snapshot::save`]
+    pub fn load(path: impl AsRef<Path>) -> HassResult<Snapshot> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| crate::HassError::GenericError(e.to_string()))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> HassResult<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).map_err(|e| crate::HassError::GenericError(e.to_string()))
+    }
+}
+
+/// The id of an entity, used by the generated code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntityId(&'static str);
+
+impl EntityId {
+    pub const fn new(entity_id: &'static str) -> EntityId {
+        EntityId(entity_id)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+
+    /// The domain of the entity, i.e. `light` for `light.kitchen`
+    pub fn domain(&self) -> &'static str {
+        EntityId::domain_of(self.0)
+    }
+
+    /// The domain part of any entity id, empty when there is no `.`
+    pub(crate) fn domain_of(entity_id: &str) -> &str {
+        entity_id
+            .split_once('.')
+            .map(|(domain, _)| domain)
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<EntityId> for Target {
+    fn from(entity_id: EntityId) -> Self {
+        Target::entity(entity_id.0)
+    }
+}
+
+/// Generates the Rust source of the `entities` and `services` modules
+pub fn generate(snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// This file is generated by hass-codegen from a Home Assistant instance, do not edit."
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    generate_entities(&mut out, &snapshot.states);
+    writeln!(out).unwrap();
+    generate_services(&mut out, &snapshot.services);
+    out
+}
+
+fn generate_entities(out: &mut String, states: &[HaState]) {
+    // Group the entities by domain, sorted to make the output stable
+    let mut domains: BTreeMap<&str, Vec<&HaState>> = BTreeMap::new();
+    for state in states {
+        domains.entry(state.domain()).or_default().push(state);
+    }
+
+    writeln!(out, "#[allow(dead_code)]").unwrap();
+    writeln!(out, "pub mod entities {{").unwrap();
+    for (index, (domain, mut states)) in domains.into_iter().enumerate() {
+        if index > 0 {
+            writeln!(out).unwrap();
+        }
+        states.sort_by(|a, b| a.entity_id.cmp(&b.entity_id));
+        writeln!(out, "    pub mod {} {{", module_name(domain)).unwrap();
+        writeln!(out, "        use r_hassclient::codegen::EntityId;").unwrap();
+
+        let mut used = HashSet::from(["ALL".to_owned()]);
+        let mut names = Vec::new();
+        for state in states {
+            let object_id = state
+                .entity_id
+                .split_once('.')
+                .map(|(_, object_id)| object_id)
+                .unwrap_or(&state.entity_id);
+            let name = unique(const_name(object_id), &mut used);
+            writeln!(out).unwrap();
+            let friendly_name = state
+                .attributes
+                .as_ref()
+                .and_then(|attributes| attributes.get("friendly_name"))
+                .and_then(Value::as_str);
+            if let Some(friendly_name) = friendly_name {
+                write_doc(out, 8, friendly_name);
+            }
+            writeln!(
+                out,
+                "        pub const {}: EntityId = EntityId::new({:?});",
+                name, state.entity_id
+            )
+            .unwrap();
+            names.push(name);
+        }
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "        pub const ALL: &[EntityId] = &[{}];",
+            names.join(", ")
+        )
+        .unwrap();
+        writeln!(out, "    }}").unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+}
+
+fn generate_services(out: &mut String, services: &HaServices) {
+    writeln!(
+        out,
+        "#[allow(dead_code, unused_imports, non_camel_case_types, clippy::all)]"
+    )
+    .unwrap();
+    writeln!(out, "pub mod services {{").unwrap();
+    for (index, (domain, services)) in services.iter().enumerate() {
+        if index > 0 {
+            writeln!(out).unwrap();
+        }
+        writeln!(out, "    pub mod {} {{", module_name(domain)).unwrap();
+        writeln!(
+            out,
+            "        use r_hassclient::services::{{ServiceCall, Target}};"
+        )
+        .unwrap();
+        let mut used = HashSet::from(["ServiceCall".to_owned(), "Target".to_owned()]);
+        for (service, description) in services {
+            generate_service(out, domain, service, description, &mut used);
+        }
+        writeln!(out, "    }}").unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+}
+
+fn generate_service(
+    out: &mut String,
+    domain: &str,
+    service: &str,
+    description: &ServiceDescription,
+    used: &mut HashSet<String>,
+) {
+    let fn_name = unique(module_name(service), used);
+    let struct_name = unique(type_name(service), used);
+    let fields = description.all_fields();
+
+    // Required fields are parameters of the function, the optional ones are builder methods
+    let mut params = Vec::new();
+    let mut required = Vec::new();
+    if description.target.is_some() {
+        params.push("target: impl Into<Target>".to_owned());
+    }
+    let mut param_names = HashSet::from(["target".to_owned()]);
+    for (field, info) in fields.iter().filter(|(_, info)| info.required) {
+        let param = unique(module_name(field), &mut param_names);
+        let field_type = FieldType::from_selector(info);
+        params.push(format!("{}: {}", param, field_type.param_type()));
+        required.push(format!(
+            ".data({:?}, {})",
+            field,
+            field_type.to_value(&param)
+        ));
+    }
+
+    writeln!(out).unwrap();
+    write_service_doc(out, 8, description);
+    writeln!(
+        out,
+        "        pub fn {}({}) -> {} {{",
+        fn_name,
+        params.join(", "),
+        struct_name
+    )
+    .unwrap();
+    let target = if description.target.is_some() {
+        ".target(target)"
+    } else {
+        ""
+    };
+    writeln!(
+        out,
+        "            {}(ServiceCall::new({:?}, {:?}){}{})",
+        struct_name,
+        domain,
+        service,
+        target,
+        required.concat()
+    )
+    .unwrap();
+    writeln!(out, "        }}").unwrap();
+
+    writeln!(out).unwrap();
+    writeln!(out, "        pub struct {}(ServiceCall);", struct_name).unwrap();
+
+    let optional: Vec<_> = fields.iter().filter(|(_, info)| !info.required).collect();
+    if !optional.is_empty() {
+        writeln!(out).unwrap();
+        writeln!(out, "        impl {} {{", struct_name).unwrap();
+        let mut methods = HashSet::new();
+        for (index, (field, info)) in optional.into_iter().enumerate() {
+            if index > 0 {
+                writeln!(out).unwrap();
+            }
+            let method = unique(module_name(field), &mut methods);
+            let field_type = FieldType::from_selector(info);
+            write_field_doc(out, 12, info);
+            writeln!(
+                out,
+                "            pub fn {}(self, value: {}) -> {} {{",
+                method,
+                field_type.param_type(),
+                struct_name
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "                {}(self.0.data({:?}, {}))",
+                struct_name,
+                field,
+                field_type.to_value("value")
+            )
+            .unwrap();
+            writeln!(out, "            }}").unwrap();
+        }
+        writeln!(out, "        }}").unwrap();
+    }
+
+    writeln!(out).unwrap();
+    writeln!(out, "        impl From<{}> for ServiceCall {{", struct_name).unwrap();
+    writeln!(
+        out,
+        "            fn from(service_call: {}) -> Self {{",
+        struct_name
+    )
+    .unwrap();
+    writeln!(out, "                service_call.0").unwrap();
+    writeln!(out, "            }}").unwrap();
+    writeln!(out, "        }}").unwrap();
+}
+
+/// The Rust type of a service field, derived from the selector of the field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldType {
+    Number,
+    Boolean,
+    Text,
+    Rgb,
+    Any,
+}
+
+impl FieldType {
+    fn from_selector(field: &ServiceField) -> FieldType {
+        let selector = match field.selector.as_ref().and_then(Value::as_object) {
+            Some(selector) => selector,
+            None => return FieldType::Any,
+        };
+        // Selectors with multiple values are sent as lists
+        let multiple = selector
+            .values()
+            .any(|options| options.get("multiple") == Some(&Value::Bool(true)));
+        if multiple {
+            return FieldType::Any;
+        }
+        match selector.keys().next().map(String::as_str) {
+            Some("number") | Some("color_temp") => FieldType::Number,
+            Some("boolean") => FieldType::Boolean,
+            Some("text")
+            | Some("select")
+            | Some("entity")
+            | Some("device")
+            | Some("area")
+            | Some("icon")
+            | Some("time")
+            | Some("date")
+            | Some("datetime")
+            | Some("theme")
+            | Some("conversation_agent") => FieldType::Text,
+            Some("color_rgb") => FieldType::Rgb,
+            _ => FieldType::Any,
+        }
+    }
+
+    fn param_type(self) -> &'static str {
+        match self {
+            FieldType::Number => "f64",
+            FieldType::Boolean => "bool",
+            FieldType::Text => "&str",
+            FieldType::Rgb => "[u8; 3]",
+            FieldType::Any => "impl Into<r_hassclient::codegen::Value>",
+        }
+    }
+
+    fn to_value(self, name: &str) -> String {
+        match self {
+            FieldType::Rgb => format!("{}.to_vec()", name),
+            _ => name.to_owned(),
+        }
+    }
+}
+
+fn write_service_doc(out: &mut String, indent: usize, description: &ServiceDescription) {
+    let name = description.name.as_deref().unwrap_or_default();
+    let text = description.description.as_deref().unwrap_or_default();
+    write_doc(out, indent, name);
+    if !name.is_empty() && !text.is_empty() {
+        writeln!(out, "{:indent$}///", "", indent = indent).unwrap();
+    }
+    write_doc(out, indent, text);
+}
+
+fn write_field_doc(out: &mut String, indent: usize, field: &ServiceField) {
+    let text = field
+        .description
+        .as_deref()
+        .or(field.name.as_deref())
+        .unwrap_or_default();
+    write_doc(out, indent, text);
+}
+
+fn write_doc(out: &mut String, indent: usize, text: &str) {
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        writeln!(out, "{:indent$}/// {}", "", line, indent = indent).unwrap();
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Converts a Home Assistant id to a valid snake case Rust identifier
+fn module_name(id: &str) -> String {
+    let mut name: String = id
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            c @ ('a'..='z' | '0'..='9' | '_') => c,
+            _ => '_',
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+fn const_name(id: &str) -> String {
+    module_name(id).to_ascii_uppercase()
+}
+
+fn type_name(id: &str) -> String {
+    let name: String = module_name(id)
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("Service{}", name)
+    } else {
+        name
+    }
+}
+
+/// Makes the name unique within a module by appending a number if needed
+fn unique(name: String, used: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut counter = 2;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{}_{}", name, counter);
+        counter += 1;
+    }
+    candidate
+}
@@ -0,0 +1,94 @@
+use super::{generate, Snapshot};
+
+fn snapshot() -> Snapshot {
+    serde_json::from_str(
+        r#"
+    {
+      "states": [
+        {
+          "entity_id": "light.kitchen",
+          "state": "on",
+          "attributes": {"friendly_name": "Kitchen light"}
+        },
+        {"entity_id": "sensor.1st_floor_temperature", "state": "21.3", "attributes": {}},
+        {"entity_id": "light.bedroom", "state": "off"}
+      ],
+      "services": {
+        "light": {
+          "turn_on": {
+            "name": "Turn on",
+            "description": "Turns on one or more lights.",
+            "target": {"entity": [{"domain": ["light"]}]},
+            "fields": {
+              "transition": {"selector": {"number": {"min": 0, "max": 300}}},
+              "advanced_fields": {
+                "collapsed": true,
+                "fields": {
+                  "rgb_color": {"description": "The color in RGB format.", "selector": {"color_rgb": {}}}
+                }
+              }
+            }
+          }
+        },
+        "input_select": {
+          "select_option": {
+            "target": {},
+            "fields": {
+              "option": {"required": true, "selector": {"text": null}}
+            }
+          }
+        },
+        "notify": {
+          "mobile_app_phone": {
+            "fields": {
+              "message": {"required": true, "selector": {"text": null}},
+              "type": {"selector": {"object": null}}
+            }
+          }
+        }
+      }
+    }"#,
+    )
+    .expect("valid snapshot")
+}
+
+#[test]
+fn entities_should_be_generated_by_domain() {
+    let code = generate(&snapshot());
+
+    assert!(code.contains("    pub mod light {"));
+    assert!(code.contains(
+        "        /// Kitchen light\n        pub const KITCHEN: EntityId = EntityId::new(\"light.kitchen\");"
+    ));
+    assert!(code.contains("        pub const ALL: &[EntityId] = &[BEDROOM, KITCHEN];"));
+    assert!(code.contains(
+        "pub const _1ST_FLOOR_TEMPERATURE: EntityId = EntityId::new(\"sensor.1st_floor_temperature\");"
+    ));
+}
+
+#[test]
+fn services_should_have_typed_fields() {
+    let code = generate(&snapshot());
+
+    assert!(code.contains("        pub fn turn_on(target: impl Into<Target>) -> TurnOn {"));
+    assert!(code.contains("            pub fn transition(self, value: f64) -> TurnOn {"));
+    // Fields in collapsed sections are flattened
+    assert!(code.contains("            /// The color in RGB format."));
+    assert!(code.contains("TurnOn(self.0.data(\"rgb_color\", value.to_vec()))"));
+}
+
+#[test]
+fn required_fields_should_be_parameters() {
+    let code = generate(&snapshot());
+
+    assert!(code.contains(
+        "        pub fn select_option(target: impl Into<Target>, option: &str) -> SelectOption {"
+    ));
+    assert!(code.contains(
+        "        pub fn mobile_app_phone(message: &str) -> MobileAppPhone {\n            MobileAppPhone(ServiceCall::new(\"notify\", \"mobile_app_phone\").data(\"message\", message))"
+    ));
+    // Keywords are not valid identifiers
+    assert!(code.contains(
+        "            pub fn type_(self, value: impl Into<r_hassclient::codegen::Value>) -> MobileAppPhone {"
+    ));
+}
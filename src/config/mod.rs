@@ -0,0 +1,139 @@
+//! Finds Home Assistant and its token from the environment, see [`crate::client::HaClientBuilder::from_env`]
+
+use serde::Deserialize;
+use std::{path::PathBuf, sync::Arc};
+use url::Url;
+
+use crate::{
+    auth::{FileToken, Secret, StaticToken, SupervisorToken, TokenSource},
+    HassError, HassResult,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The websocket endpoint of Home Assistant for add-ons
+pub const SUPERVISOR_URL: &str = "ws://supervisor/core/websocket";
+
+/// A configuration file, like `{"url": "https://ha.local:8123", "token_file": "/run/secrets/ha"}`
+#[derive(Deserialize)]
+struct ConfigFile {
+    url: String,
+    token: Option<Secret>,
+    token_file: Option<PathBuf>,
+}
+
+/// The url and token source of the first configuration found
+///
+/// In order: the `HASS_URL` and `HASS_TOKEN` variables, the `SUPERVISOR_TOKEN`
+/// of an add-on, and the json file in `HASS_CONFIG` or at
+/// `~/.config/r-hassclient/config.json`.
+pub(crate) fn detect(
+    var: impl Fn(&str) -> Option<String>,
+) -> HassResult<(Url, Arc<dyn TokenSource>)> {
+    if let (Some(url), Some(token)) = (var("HASS_URL"), var("HASS_TOKEN")) {
+        return Ok((websocket_url(&url)?, Arc::new(StaticToken::new(&token))));
+    }
+    // The supervisor token is read again on every authentication
+    if var("SUPERVISOR_TOKEN").is_some() {
+        return Ok((
+            Url::parse(SUPERVISOR_URL)?,
+            Arc::new(SupervisorToken::new()),
+        ));
+    }
+    let path = var("HASS_CONFIG").map(PathBuf::from).or_else(|| {
+        var("HOME")
+            .map(|home| PathBuf::from(home).join(".config/r-hassclient/config.json"))
+            .filter(|path| path.exists())
+    });
+    match path {
+        Some(path) => read_config_file(path),
+        None => Err(HassError::GenericError(
+            "no Home Assistant configuration found, set HASS_URL and HASS_TOKEN".to_owned(),
+        )),
+    }
+}
+
+fn read_config_file(path: PathBuf) -> HassResult<(Url, Arc<dyn TokenSource>)> {
+    let contents = std::fs::read_to_string(&path).map_err(|err| {
+        HassError::GenericError(format!("can not read {}: {}", path.display(), err))
+    })?;
+    let config: ConfigFile = serde_json::from_str(&contents)?;
+    let token: Arc<dyn TokenSource> = match (config.token, config.token_file) {
+        (_, Some(token_file)) => Arc::new(FileToken::new(token_file)),
+        (Some(token), None) => Arc::new(StaticToken::new(token.expose())),
+        (None, None) => {
+            return Err(HassError::GenericError(format!(
+                "{} has no token or token_file",
+                path.display()
+            )))
+        }
+    };
+    Ok((websocket_url(&config.url)?, token))
+}
+
+/// The websocket endpoint for the address of Home Assistant
+///
+/// `http://host:8123` becomes `ws://host:8123/api/websocket` and `https`
+/// becomes `wss`. `api/websocket` is joined onto the path, so Home Assistant
+/// behind a path prefix like `http://host/ha/` is reached at
+/// `ws://host/ha/api/websocket`. A path already ending in `/websocket`, like
+/// the add-on endpoint, is kept.
+///
+/// ```
+/// use r_hassclient::config::websocket_url;
+///
+/// let url = websocket_url("https://ha.example.com").unwrap();
+/// assert_eq!(url.as_str(), "wss://ha.example.com/api/websocket");
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if the address is not a http or websocket url.
+pub fn websocket_url(address: &str) -> HassResult<Url> {
+    let mut url = Url::parse(address.trim())?;
+    let scheme = match url.scheme() {
+        "http" | "ws" => "ws",
+        "https" | "wss" => "wss",
+        scheme => {
+            return Err(HassError::GenericError(format!(
+                "unsupported scheme {} in {}",
+                scheme, address
+            )))
+        }
+    };
+    url.set_scheme(scheme)
+        .map_err(|_| HassError::GenericError(format!("can not use {}", address)))?;
+    let base = url.path().trim_end_matches('/').to_owned();
+    if !base.ends_with("/websocket") {
+        url.set_path(&format!("{}/api/websocket", base));
+    }
+    Ok(url)
+}
+
+/// The base url of the REST api for a websocket or http url of Home Assistant
+///
+/// `wss://host:8123/api/websocket` becomes `https://host:8123/` and the add-on
+/// endpoint `ws://supervisor/core/websocket` becomes `http://supervisor/core/`.
+#[cfg(any(feature = "rest", feature = "oauth"))]
+pub(crate) fn rest_url(url: &Url) -> Url {
+    let mut url = url.clone();
+    let scheme = match url.scheme() {
+        "ws" => "http",
+        "wss" => "https",
+        scheme => scheme,
+    }
+    .to_owned();
+    // both schemes are special, switching between them always succeeds
+    _ = url.set_scheme(&scheme);
+    let path = url.path();
+    let path = path
+        .strip_suffix("api/websocket")
+        .or_else(|| path.strip_suffix("websocket"))
+        .unwrap_or(path)
+        .trim_end_matches('/')
+        .to_owned();
+    url.set_path(&format!("{}/", path));
+    url.set_query(None);
+    url
+}
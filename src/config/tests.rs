@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use super::*;
+
+fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    move |name| vars.get(name).cloned()
+}
+
+#[test]
+fn http_address_should_become_websocket_url() {
+    let cases = [
+        ("http://localhost:8123", "ws://localhost:8123/api/websocket"),
+        (
+            "https://ha.example.com/",
+            "wss://ha.example.com/api/websocket",
+        ),
+        (
+            "https://example.com/ha",
+            "wss://example.com/ha/api/websocket",
+        ),
+        (
+            "http://ha.local/api/websocket",
+            "ws://ha.local/api/websocket",
+        ),
+        ("ws://ha.local:8123", "ws://ha.local:8123/api/websocket"),
+        ("http://host/ha/", "ws://host/ha/api/websocket"),
+        ("ws://host/ha/", "ws://host/ha/api/websocket"),
+        (
+            "wss://proxy.local/custom",
+            "wss://proxy.local/custom/api/websocket",
+        ),
+        (
+            "https://host/ha/api/websocket/",
+            "wss://host/ha/api/websocket/",
+        ),
+        (SUPERVISOR_URL, SUPERVISOR_URL),
+    ];
+
+    for (address, expected) in cases {
+        assert_eq!(websocket_url(address).unwrap().as_str(), expected);
+    }
+    assert!(websocket_url("ftp://ha.local").is_err());
+}
+
+#[tokio::test]
+async fn hass_variables_should_be_used_first() {
+    let (url, token) = detect(vars(&[
+        ("HASS_URL", "http://ha.local:8123"),
+        ("HASS_TOKEN", "token"),
+        ("SUPERVISOR_TOKEN", "supervisor"),
+    ]))
+    .unwrap();
+
+    assert_eq!(url.as_str(), "ws://ha.local:8123/api/websocket");
+    assert_eq!(token.token().await.unwrap().expose(), "token");
+}
+
+#[tokio::test]
+async fn addon_should_use_supervisor() {
+    let (url, token) = detect(vars(&[("SUPERVISOR_TOKEN", "supervisor")])).unwrap();
+
+    assert_eq!(url.as_str(), SUPERVISOR_URL);
+    // The token comes from the environment at authentication, not from detection
+    std::env::set_var("SUPERVISOR_TOKEN", "first");
+    assert_eq!(token.token().await.unwrap().expose(), "first");
+    std::env::set_var("SUPERVISOR_TOKEN", "rotated");
+    assert_eq!(token.token().await.unwrap().expose(), "rotated");
+}
+
+#[tokio::test]
+async fn config_file_should_be_read() {
+    let dir = std::env::temp_dir().join(format!("r-hassclient-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.json");
+    std::fs::write(
+        &path,
+        r#"{"url": "https://ha.local", "token": "from file"}"#,
+    )
+    .unwrap();
+
+    let (url, token) = detect(vars(&[("HASS_CONFIG", path.to_str().unwrap())])).unwrap();
+
+    assert_eq!(url.as_str(), "wss://ha.local/api/websocket");
+    assert_eq!(token.token().await.unwrap().expose(), "from file");
+}
+
+#[test]
+fn missing_configuration_should_fail() {
+    assert!(detect(vars(&[("HASS_URL", "http://ha.local")])).is_err());
+}
+
+#[cfg(any(feature = "rest", feature = "oauth"))]
+#[test]
+fn websocket_url_should_become_rest_url() {
+    let cases = [
+        (
+            "ws://localhost:8123/api/websocket",
+            "http://localhost:8123/",
+        ),
+        (
+            "wss://example.com/ha/api/websocket",
+            "https://example.com/ha/",
+        ),
+        (SUPERVISOR_URL, "http://supervisor/core/"),
+        ("http://localhost:8123", "http://localhost:8123/"),
+    ];
+
+    for (address, expected) in cases {
+        let url = Url::parse(address).unwrap();
+        assert_eq!(rest_url(&url).as_str(), expected);
+    }
+}
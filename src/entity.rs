@@ -0,0 +1,144 @@
+use serde_json::Value;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use crate::{
+    client::HaConnection, codegen::EntityId, services::ServiceCall, HaEventData, HaState,
+    HassError, HassResult, StateChangedEvent, WsEvent,
+};
+
+/// A handle to a single entity in Home Assistant
+///
+/// The handle is created with [`HaConnection::entity`] and borrows the
+/// connection for as long as it is used.
+///
+/// ```no_run
+/// # async fn example(conn: &mut r_hassclient::client::HaConnection) -> r_hassclient::HassResult<()> {
+/// let mut kitchen = conn.entity("light.kitchen");
+/// kitchen.turn_on().await?;
+/// if let Some(state) = kitchen.state().await? {
+///     println!("kitchen light is {}", state.state);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Entity<'a> {
+    conn: &'a mut HaConnection,
+    entity_id: String,
+}
+
+impl<'a> Entity<'a> {
+    pub(crate) fn new(conn: &'a mut HaConnection, entity_id: &str) -> Entity<'a> {
+        Entity {
+            conn,
+            entity_id: entity_id.to_owned(),
+        }
+    }
+
+    pub fn entity_id(&self) -> &str {
+        &self.entity_id
+    }
+
+    /// The domain of the entity, i.e. `light` for `light.kitchen`
+    pub fn domain(&self) -> &str {
+        EntityId::domain_of(&self.entity_id)
+    }
+
+    /// Reads the latest state of the entity from Home Assistant
+    ///
+    /// Returns `None` if the entity does not exist.
+    pub async fn state(&mut self) -> HassResult<Option<HaState>> {
+        let states = self.conn.get_states().await?;
+        Ok(states
+            .into_iter()
+            .find(|state| state.entity_id == self.entity_id))
+    }
+
+    /// Subscribes to the state changes of the entity
+    ///
+    /// Every call adds a `state_changed` callback, the changes of other
+    /// entities are filtered out before they reach the receiver. Returns the
+    /// id of the callback with the receiver, pass the id to
+    /// [`Entity::unsubscribe`] or [`HaConnection::unsubscribe`] when done.
+    pub async fn changes(&mut self) -> HassResult<(u64, UnboundedReceiver<StateChangedEvent>)> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let entity_id = self.entity_id.clone();
+
+        let listener_id = self
+            .conn
+            .subscribe_event("state_changed", move |item: WsEvent| {
+                if let Ok(HaEventData::StateChangedEvent(event)) = item.event.event_data() {
+                    if event.entity_id == entity_id {
+                        // The receiver is dropped when the client is not interested anymore
+                        _ = tx.send(event.clone());
+                    }
+                }
+            })
+            .await?;
+        Ok((listener_id, rx))
+    }
+
+    /// Stops the changes returned by [`Entity::changes`]
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if Home Assistant fails to cancel the subscription.
+    pub async fn unsubscribe(&mut self, listener_id: u64) -> HassResult<()> {
+        self.conn.unsubscribe(listener_id).await
+    }
+
+    /// Calls the `turn_on` service in the domain of the entity
+    pub async fn turn_on(&mut self) -> HassResult<String> {
+        self.call_service("turn_on", None).await
+    }
+
+    /// Calls the `turn_off` service in the domain of the entity
+    pub async fn turn_off(&mut self) -> HassResult<String> {
+        self.call_service("turn_off", None).await
+    }
+
+    /// Calls the `toggle` service in the domain of the entity
+    pub async fn toggle(&mut self) -> HassResult<String> {
+        self.call_service("toggle", None).await
+    }
+
+    /// Sets the value of number, text and select entities
+    ///
+    /// Select entities use the `select_option` service, all other domains use
+    /// the `set_value` service.
+    pub async fn set_value(&mut self, value: impl Into<Value>) -> HassResult<String> {
+        let service_call = match self.domain() {
+            "input_select" | "select" => ServiceCall::new(self.domain(), "select_option")
+                .target(&self.entity_id)
+                .data("option", value),
+            _ => ServiceCall::new(self.domain(), "set_value")
+                .target(&self.entity_id)
+                .data("value", value),
+        };
+        self.conn.call(service_call).await
+    }
+
+    /// Calls any service in the domain of the entity with the entity as target
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the service data is not a json
+    /// object or the service call fails.
+    pub async fn call_service(
+        &mut self,
+        service: &str,
+        service_data: Option<Value>,
+    ) -> HassResult<String> {
+        let mut service_call = ServiceCall::new(self.domain(), service).target(&self.entity_id);
+        match service_data {
+            Some(Value::Object(data)) => service_call.data = data,
+            Some(data) => {
+                return Err(HassError::GenericError(format!(
+                    "the service data must be a json object, got {}",
+                    data
+                )))
+            }
+            None => {}
+        }
+        self.conn.call(service_call).await
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt;
 use tokio::sync::mpsc::error::SendError;
 use tokio_tungstenite::tungstenite::Error as TungsteniteError;
 
-use crate::WsResult;
+use crate::{HaError, HaErrorCode, WsResult};
 
 //pub (crate) type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
@@ -34,10 +34,40 @@ pub enum HassError {
     GenericError(String),
     UnknownPayloadReceived,
     ResponseError(WsResult),
+
+    /// Returned when Home Assistant did not reach the expected state in time
+    Timeout(String),
+
+    /// Returned when the TLS configuration is invalid or the TLS handshake fails
+    Tls(String),
+
+    /// Returned when the REST api answers with an error status, with the body
+    HttpError(u16, String),
+
+    /// Returned when a state is viewed as a domain the entity does not belong to
+    DomainMismatch {
+        expected: String,
+        entity_id: String,
+    },
 }
 
 impl std::error::Error for HassError {}
 
+impl HassError {
+    /// The error returned by Home Assistant, if this is a response error
+    pub fn ha_error(&self) -> Option<&HaError> {
+        match self {
+            Self::ResponseError(result) => result.error(),
+            _ => None,
+        }
+    }
+
+    /// The Home Assistant error code, if this is a response error
+    pub fn error_code(&self) -> Option<&HaErrorCode> {
+        self.ha_error().map(|error| &error.code)
+    }
+}
+
 impl fmt::Display for HassError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -55,13 +85,26 @@ impl fmt::Display for HassError {
             Self::SendError(e) => write!(f, "Send Error: {}", e),
             // Self::ChannelSend(e) => write!(f, "Channel Send Error: {}", e),
             Self::UnknownPayloadReceived => write!(f, "The received payload is unknown"),
-            Self::ResponseError(e) => write!(
+            Self::ResponseError(e) => match e.error() {
+                Some(error) => write!(
+                    f,
+                    "The error code:{} with the error message: {}",
+                    error.code, error.message
+                ),
+                None => write!(f, "The command {} failed without an error message", e.id),
+            },
+            Self::GenericError(detail) => write!(f, "Generic Error: {}", detail),
+            Self::Timeout(detail) => write!(f, "Timed out {}", detail),
+            Self::Tls(detail) => write!(f, "TLS Error: {}", detail),
+            Self::HttpError(status, body) => write!(f, "HTTP Error {}: {}", status, body),
+            Self::DomainMismatch {
+                expected,
+                entity_id,
+            } => write!(
                 f,
-                "The error code:{} with the error message: {}",
-                e.error.as_ref().unwrap().code,
-                e.error.as_ref().unwrap().message
+                "The entity {} is not in the {} domain",
+                entity_id, expected
             ),
-            Self::GenericError(detail) => write!(f, "Generic Error: {}", detail),
         }
     }
 }
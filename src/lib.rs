@@ -1,8 +1,42 @@
+// `HassError` keeps the tungstenite error inline, boxing it would change the public variant
+#![allow(clippy::result_large_err)]
+
 pub mod errors;
 pub use errors::{HassError, HassResult};
 
 pub mod types;
 pub use types::*;
 
+pub mod services;
+
+pub mod entity;
+pub use entity::Entity;
+
+pub mod codegen;
+
+pub mod listener;
+pub use listener::SlowConsumerPolicy;
+
+pub mod store;
+pub use store::{Query, StateStore, StateStreamExt};
+
+pub mod auth;
+
+pub mod config;
+
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+pub mod tls;
+
+#[cfg(feature = "rest")]
+pub mod rest;
+#[cfg(feature = "rest")]
+pub use rest::HaRestClient;
+
+pub mod transport;
+pub use transport::Transport;
+
+#[cfg(feature = "mock")]
+pub mod mock;
+
 pub mod client;
 pub use client::HaClient;
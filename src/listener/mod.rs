@@ -0,0 +1,249 @@
+use futures_util::FutureExt;
+use std::{
+    any::Any,
+    collections::HashMap,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+};
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::{store::glob_match, WsEvent};
+
+#[cfg(test)]
+mod tests;
+
+/// The key of the subscription to all events, event types never contain `*`
+pub(crate) const ALL_EVENTS: &str = "*";
+
+/// The default number of events queued for a subscription
+pub(crate) const DEFAULT_CAPACITY: usize = 128;
+
+/// What to do with an event when the queue of a subscription is full
+///
+/// Every subscription has its own task and queue, so a slow callback never
+/// stalls the other subscriptions or the command responses, unless the
+/// policy is [`SlowConsumerPolicy::Wait`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SlowConsumerPolicy {
+    /// Drops the new event, the callback keeps running
+    #[default]
+    DropNewest,
+    /// Waits for room in the queue, this stalls all traffic from Home Assistant
+    Wait,
+    /// Removes the callback, it receives no more events
+    Disconnect,
+}
+
+/// The subscriptions in Home Assistant and the local listeners sharing them
+///
+/// Listeners of the same event type share one subscription in Home Assistant,
+/// every event is queued for each of them. The subscription is only cancelled
+/// when the last listener is removed.
+///
+/// Only event subscriptions are shared, the client has no trigger
+/// subscriptions (`subscribe_trigger`) to share by trigger definition.
+#[derive(Default)]
+pub(crate) struct Subscriptions {
+    last_listener_id: u64,
+    by_event_type: HashMap<String, u64>,
+    shared: HashMap<u64, Shared>,
+    // subscriptions left without listeners by the slow consumer policy, not cancelled yet
+    cancelled: Vec<u64>,
+}
+
+struct Shared {
+    event_type: String,
+    listeners: HashMap<u64, Listener>,
+}
+
+impl Subscriptions {
+    /// The subscription in Home Assistant for the event type, if there is one
+    pub(crate) fn find(&self, event_type: &str) -> Option<u64> {
+        self.by_event_type.get(event_type).copied()
+    }
+
+    /// Reserves the id of the next listener
+    pub(crate) fn next_listener_id(&mut self) -> u64 {
+        self.last_listener_id += 1;
+        self.last_listener_id
+    }
+
+    /// Adds a listener to the subscription in Home Assistant
+    pub(crate) fn add(
+        &mut self,
+        subscription: u64,
+        event_type: &str,
+        listener_id: u64,
+        listener: Listener,
+    ) {
+        self.by_event_type
+            .insert(event_type.to_owned(), subscription);
+        self.shared
+            .entry(subscription)
+            .or_insert_with(|| Shared {
+                event_type: event_type.to_owned(),
+                listeners: HashMap::new(),
+            })
+            .listeners
+            .insert(listener_id, listener);
+    }
+
+    /// Removes the listener
+    ///
+    /// Returns the subscription in Home Assistant when it was the last
+    /// listener, the subscription should then be cancelled.
+    pub(crate) fn remove(&mut self, listener_id: u64) -> Option<u64> {
+        let (&subscription, shared) = self
+            .shared
+            .iter_mut()
+            .find(|(_, shared)| shared.listeners.contains_key(&listener_id))?;
+        shared.listeners.remove(&listener_id);
+        if !shared.listeners.is_empty() {
+            return None;
+        }
+        let shared = self.shared.remove(&subscription)?;
+        self.by_event_type.remove(&shared.event_type);
+        Some(subscription)
+    }
+
+    /// Removes a listener that fell behind
+    ///
+    /// Listeners fall behind while events are received, when no command can
+    /// be sent. When it was the last listener, the subscription is kept for
+    /// [`Subscriptions::take_cancelled`] and cancelled with the next command.
+    pub(crate) fn disconnect(&mut self, subscription: u64, listener_id: u64) {
+        let Some(shared) = self.shared.get_mut(&subscription) else {
+            return;
+        };
+        shared.listeners.remove(&listener_id);
+        if !shared.listeners.is_empty() {
+            return;
+        }
+        if let Some(shared) = self.shared.remove(&subscription) {
+            self.by_event_type.remove(&shared.event_type);
+            self.cancelled.push(subscription);
+        }
+    }
+
+    /// The subscriptions in Home Assistant left without listeners, to cancel
+    pub(crate) fn take_cancelled(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.cancelled)
+    }
+
+    /// The listeners of the subscription in Home Assistant
+    pub(crate) fn listeners(&self, subscription: u64) -> Vec<(u64, Listener)> {
+        self.shared
+            .get(&subscription)
+            .map(|shared| {
+                shared
+                    .listeners
+                    .iter()
+                    .map(|(id, listener)| (*id, listener.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// The callback of a subscription, running on its own task
+#[derive(Clone)]
+pub(crate) struct Listener {
+    id: u64,
+    queue: mpsc::Sender<WsEvent>,
+    policy: SlowConsumerPolicy,
+    // the patterns of the event types to queue, all events are queued when empty
+    event_types: Arc<[String]>,
+}
+
+impl Listener {
+    /// Spawns the task that runs the callback for each event in the queue
+    ///
+    /// A panic in the callback is caught and printed to stderr, it is not
+    /// returned anywhere. The task keeps handling the next events.
+    pub(crate) fn spawn<F, Fut>(
+        listener_id: u64,
+        callback: F,
+        capacity: usize,
+        policy: SlowConsumerPolicy,
+    ) -> Listener
+    where
+        F: Fn(WsEvent) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (queue, mut events) = mpsc::channel::<WsEvent>(capacity.max(1));
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                // The callback is only borrowed to create the future, so it does not need to be Sync
+                let result = match panic::catch_unwind(AssertUnwindSafe(|| callback(event))) {
+                    Ok(future) => AssertUnwindSafe(future).catch_unwind().await,
+                    Err(panic) => Err(panic),
+                };
+                if let Err(panic) = result {
+                    eprintln!(
+                        "Callback of listener {} panicked: {}",
+                        listener_id,
+                        panic_message(&panic)
+                    );
+                }
+            }
+        });
+        Listener {
+            id: listener_id,
+            queue,
+            policy,
+            event_types: Arc::from([]),
+        }
+    }
+
+    /// Only queues the events with a type matching one of the patterns
+    ///
+    /// The patterns support `*` and `?` wildcards, like `automation_*`.
+    pub(crate) fn with_event_types(mut self, patterns: &[&str]) -> Listener {
+        self.event_types = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        self
+    }
+
+    fn accepts(&self, event: &WsEvent) -> bool {
+        self.event_types.is_empty()
+            || self
+                .event_types
+                .iter()
+                .any(|pattern| glob_match(pattern, &event.event.event_type))
+    }
+
+    /// Queues the event for the callback
+    ///
+    /// Returns `false` when the listener should be removed, because of the
+    /// slow consumer policy or because the task is gone.
+    pub(crate) async fn dispatch(&self, event: WsEvent) -> bool {
+        if !self.accepts(&event) {
+            return true;
+        }
+        match self.queue.try_send(event) {
+            Ok(()) => true,
+            Err(TrySendError::Closed(_)) => false,
+            Err(TrySendError::Full(event)) => match self.policy {
+                SlowConsumerPolicy::DropNewest => {
+                    eprintln!("Dropped event for slow listener {}", self.id);
+                    true
+                }
+                SlowConsumerPolicy::Wait => self.queue.send(event).await.is_ok(),
+                SlowConsumerPolicy::Disconnect => {
+                    eprintln!("Removed slow listener {}", self.id);
+                    false
+                }
+            },
+        }
+    }
+}
+
+fn panic_message(panic: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
@@ -0,0 +1,206 @@
+use serde_json::json;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use tokio::{
+    sync::{mpsc, Notify},
+    time::{timeout, Duration},
+};
+
+use super::{Listener, SlowConsumerPolicy, Subscriptions};
+use crate::WsEvent;
+
+fn event(id: u64) -> WsEvent {
+    typed_event(id, "state_changed")
+}
+
+fn typed_event(id: u64, event_type: &str) -> WsEvent {
+    serde_json::from_value(json!({
+        "id": id,
+        "event": {
+            "data": {},
+            "event_type": event_type,
+            "time_fired": "2024-01-01T00:00:00+00:00",
+            "origin": "LOCAL"
+        }
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn panicking_callback_should_keep_receiving_events() {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let listener = Listener::spawn(
+        1,
+        move |_event| {
+            let tx = tx.clone();
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if call == 0 {
+                    panic!("first event fails");
+                }
+                tx.send(call).unwrap();
+            }
+        },
+        8,
+        SlowConsumerPolicy::DropNewest,
+    );
+
+    assert!(listener.dispatch(event(1)).await);
+    assert!(listener.dispatch(event(1)).await);
+
+    let call = timeout(Duration::from_secs(1), rx.recv()).await.unwrap();
+    assert_eq!(call, Some(1));
+}
+
+fn blocked_listener(policy: SlowConsumerPolicy) -> (Listener, Arc<Notify>, Arc<AtomicUsize>) {
+    let release = Arc::new(Notify::new());
+    let handled = Arc::new(AtomicUsize::new(0));
+    let (wait, count) = (Arc::clone(&release), Arc::clone(&handled));
+    let listener = Listener::spawn(
+        1,
+        move |_event| {
+            let (wait, count) = (Arc::clone(&wait), Arc::clone(&count));
+            async move {
+                wait.notified().await;
+                count.fetch_add(1, Ordering::SeqCst);
+            }
+        },
+        1,
+        policy,
+    );
+    (listener, release, handled)
+}
+
+#[tokio::test]
+async fn full_queue_should_drop_newest_event() {
+    let (listener, _release, _handled) = blocked_listener(SlowConsumerPolicy::DropNewest);
+
+    // The first event is taken by the callback, the second fills the queue
+    assert!(listener.dispatch(event(1)).await);
+    tokio::task::yield_now().await;
+    assert!(listener.dispatch(event(1)).await);
+    assert!(listener.dispatch(event(1)).await);
+    assert_eq!(listener.queue.capacity(), 0);
+}
+
+#[tokio::test]
+async fn full_queue_should_disconnect_slow_listener() {
+    let (listener, _release, _handled) = blocked_listener(SlowConsumerPolicy::Disconnect);
+
+    assert!(listener.dispatch(event(1)).await);
+    tokio::task::yield_now().await;
+    assert!(listener.dispatch(event(1)).await);
+    assert!(!listener.dispatch(event(1)).await);
+}
+
+#[tokio::test]
+async fn full_queue_should_wait_for_slow_listener() {
+    let (listener, release, handled) = blocked_listener(SlowConsumerPolicy::Wait);
+
+    assert!(listener.dispatch(event(1)).await);
+    tokio::task::yield_now().await;
+    assert!(listener.dispatch(event(1)).await);
+
+    let waiting = timeout(Duration::from_millis(50), listener.dispatch(event(1))).await;
+    assert!(waiting.is_err());
+
+    release.notify_one();
+    let delivered = timeout(Duration::from_secs(1), listener.dispatch(event(1))).await;
+    assert_eq!(delivered, Ok(true));
+    assert_eq!(handled.load(Ordering::SeqCst), 1);
+}
+
+fn idle_listener() -> Listener {
+    Listener::spawn(0, |_event| async {}, 8, SlowConsumerPolicy::DropNewest)
+}
+
+#[tokio::test]
+async fn listeners_of_same_event_type_should_share_subscription() {
+    let mut subscriptions = Subscriptions::default();
+    let first = subscriptions.next_listener_id();
+    subscriptions.add(7, "state_changed", first, idle_listener());
+
+    assert_eq!(subscriptions.find("state_changed"), Some(7));
+    assert_eq!(subscriptions.find("call_service"), None);
+
+    let second = subscriptions.next_listener_id();
+    subscriptions.add(7, "state_changed", second, idle_listener());
+
+    assert_ne!(first, second);
+    assert_eq!(subscriptions.listeners(7).len(), 2);
+}
+
+#[tokio::test]
+async fn removing_last_listener_should_cancel_subscription() {
+    let mut subscriptions = Subscriptions::default();
+    let first = subscriptions.next_listener_id();
+    subscriptions.add(7, "state_changed", first, idle_listener());
+    let second = subscriptions.next_listener_id();
+    subscriptions.add(7, "state_changed", second, idle_listener());
+
+    assert_eq!(subscriptions.remove(first), None);
+    assert_eq!(subscriptions.listeners(7).len(), 1);
+
+    assert_eq!(subscriptions.remove(second), Some(7));
+    assert_eq!(subscriptions.find("state_changed"), None);
+    assert_eq!(subscriptions.remove(second), None);
+}
+
+#[tokio::test]
+async fn disconnected_listener_should_keep_shared_subscription() {
+    let mut subscriptions = Subscriptions::default();
+    let first = subscriptions.next_listener_id();
+    subscriptions.add(7, "state_changed", first, idle_listener());
+    let second = subscriptions.next_listener_id();
+    subscriptions.add(7, "state_changed", second, idle_listener());
+
+    subscriptions.disconnect(7, first);
+
+    assert_eq!(subscriptions.listeners(7).len(), 1);
+    assert_eq!(subscriptions.find("state_changed"), Some(7));
+    assert!(subscriptions.take_cancelled().is_empty());
+}
+
+#[tokio::test]
+async fn disconnecting_last_listener_should_cancel_subscription() {
+    let mut subscriptions = Subscriptions::default();
+    let listener_id = subscriptions.next_listener_id();
+    subscriptions.add(7, "state_changed", listener_id, idle_listener());
+
+    subscriptions.disconnect(7, listener_id);
+
+    assert!(subscriptions.listeners(7).is_empty());
+    assert_eq!(subscriptions.find("state_changed"), None);
+    assert_eq!(subscriptions.take_cancelled(), vec![7]);
+    assert!(subscriptions.take_cancelled().is_empty());
+    assert_eq!(subscriptions.remove(listener_id), None);
+}
+
+#[tokio::test]
+async fn listener_should_only_receive_matching_event_types() {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let listener = Listener::spawn(
+        1,
+        move |event: WsEvent| {
+            let tx = tx.clone();
+            async move {
+                tx.send(event.event.event_type).unwrap();
+            }
+        },
+        8,
+        SlowConsumerPolicy::DropNewest,
+    )
+    .with_event_types(&["automation_*", "call_service"]);
+
+    for event_type in ["state_changed", "automation_triggered", "call_service"] {
+        assert!(listener.dispatch(typed_event(1, event_type)).await);
+    }
+
+    assert_eq!(rx.recv().await.unwrap(), "automation_triggered");
+    assert_eq!(rx.recv().await.unwrap(), "call_service");
+    assert!(rx.try_recv().is_err());
+}
@@ -0,0 +1,529 @@
+//! An in-process Home Assistant server for tests without Docker
+//!
+//! The server speaks the websocket api of Home Assistant: the auth handshake,
+//! `result`, `event` and `pong` messages. It holds a table of entities that
+//! tests can script, and calling a service changes the state of the targeted
+//! entities and fires `state_changed` events, like Home Assistant does.
+//!
+//! ```
+//! # async fn example() -> r_hassclient::HassResult<()> {
+//! use r_hassclient::{mock::MockServer, HaClient};
+//! use serde_json::json;
+//!
+//! let server = MockServer::start().await?;
+//! server.set_state("light.kitchen", "off", json!({"friendly_name": "Kitchen"}));
+//!
+//! let mut conn = HaClient::builder().build().connect_async(server.url()).await?;
+//! conn.authenticate_with_token("any token").await?;
+//! conn.entity("light.kitchen").turn_on().await?;
+//!
+//! assert_eq!(server.state("light.kitchen").unwrap().state, "on");
+//! # Ok(())
+//! # }
+//! ```
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    transport::{MemoryTransport, Transport},
+    HaState, HassError, HassResult,
+};
+
+/// A fault injected in the messages sent to the client
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    /// Closes the connection instead of sending the message
+    Drop,
+    /// Waits before sending the message
+    Delay(Duration),
+    /// Sends a frame that is not valid json instead of the message
+    Malformed,
+}
+
+/// A service call received by the mock server
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockServiceCall {
+    pub domain: String,
+    pub service: String,
+    pub service_data: Value,
+    pub entity_ids: Vec<String>,
+}
+
+/// A mock Home Assistant, cheap to clone and shared by all its connections
+#[derive(Clone, Default)]
+pub struct MockServer {
+    state: Arc<Mutex<MockState>>,
+    addr: Option<SocketAddr>,
+}
+
+#[derive(Default)]
+struct MockState {
+    // the token to accept, any token is accepted when not set
+    token: Option<String>,
+    entities: BTreeMap<String, HaState>,
+    services: Value,
+    calls: Vec<MockServiceCall>,
+    faults: VecDeque<Fault>,
+    connections: HashMap<usize, Connection>,
+    last_connection: usize,
+    last_context: u64,
+}
+
+struct Connection {
+    outbox: mpsc::UnboundedSender<Message>,
+    // the event type of each subscription, `None` for all events
+    subscriptions: HashMap<u64, Option<String>>,
+}
+
+impl MockServer {
+    /// Creates a server without listening on a socket, use [`MockServer::connect`]
+    pub fn new() -> MockServer {
+        MockServer::default()
+    }
+
+    /// Starts a server listening on a local port
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no local port can be bound.
+    pub async fn start() -> HassResult<MockServer> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|err| HassError::GenericError(err.to_string()))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|err| HassError::GenericError(err.to_string()))?;
+
+        let server = MockServer {
+            addr: Some(addr),
+            ..MockServer::default()
+        };
+        let accepting = server.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let server = accepting.clone();
+                tokio::spawn(async move {
+                    if let Ok(ws) = tokio_tungstenite::accept_async(socket).await {
+                        server.serve(ws).await;
+                    }
+                });
+            }
+        });
+        Ok(server)
+    }
+
+    /// The websocket url of a started server
+    ///
+    /// # Panics
+    ///
+    /// Panics if the server was created with [`MockServer::new`].
+    pub fn url(&self) -> url::Url {
+        let addr = self.addr.expect("the mock server is not started");
+        url::Url::parse(&format!("ws://{}/api/websocket", addr)).expect("valid url")
+    }
+
+    /// Connects an in-memory transport to the server
+    pub fn connect(&self) -> MemoryTransport {
+        let (client, server) = MemoryTransport::pair();
+        let mock = self.clone();
+        tokio::spawn(async move { mock.serve(server).await });
+        client
+    }
+
+    /// Only accepts this token, any token is accepted by default
+    pub fn set_token(&self, token: &str) {
+        self.lock().token = Some(token.to_owned());
+    }
+
+    /// Sets the state of the entity and fires a `state_changed` event
+    pub fn set_state(&self, entity_id: &str, state: &str, attributes: Value) {
+        let attributes = match attributes {
+            Value::Object(attributes) => Some(attributes.into_iter().collect()),
+            _ => None,
+        };
+        let mut mock = self.lock();
+        let old_state = mock.entities.get(entity_id).cloned();
+        let new_state = HaState {
+            entity_id: entity_id.to_owned(),
+            attributes,
+            state: state.to_owned(),
+            ..Default::default()
+        };
+        mock.change_state(old_state, new_state, None);
+    }
+
+    /// Removes the entity and fires a `state_changed` event without new state
+    pub fn remove_state(&self, entity_id: &str) {
+        let mut mock = self.lock();
+        if let Some(old_state) = mock.entities.remove(entity_id) {
+            let data = json!({"entity_id": entity_id, "old_state": old_state, "new_state": null});
+            mock.fire("state_changed", data);
+        }
+    }
+
+    /// The current state of the entity
+    pub fn state(&self, entity_id: &str) -> Option<HaState> {
+        self.lock().entities.get(entity_id).cloned()
+    }
+
+    /// Fires an event to the subscribed clients
+    pub fn fire_event(&self, event_type: &str, data: Value) {
+        self.lock().fire(event_type, data);
+    }
+
+    /// Sets the response to `get_services`
+    pub fn set_services(&self, services: Value) {
+        self.lock().services = services;
+    }
+
+    /// The service calls received so far
+    pub fn service_calls(&self) -> Vec<MockServiceCall> {
+        self.lock().calls.clone()
+    }
+
+    /// The number of event subscriptions of all connections
+    pub fn subscription_count(&self) -> usize {
+        self.lock()
+            .connections
+            .values()
+            .map(|connection| connection.subscriptions.len())
+            .sum()
+    }
+
+    /// Injects a fault in the next message sent to a client, faults are applied in order
+    pub fn inject_fault(&self, fault: Fault) {
+        self.lock().faults.push_back(fault);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn serve(&self, transport: impl Transport) {
+        let (mut sink, mut stream) = transport.split();
+        let (outbox, mut messages) = mpsc::unbounded_channel::<Message>();
+
+        let writer = self.clone();
+        tokio::spawn(async move {
+            while let Some(message) = messages.recv().await {
+                let fault = writer.lock().faults.pop_front();
+                let message = match fault {
+                    None => message,
+                    Some(Fault::Delay(delay)) => {
+                        tokio::time::sleep(delay).await;
+                        message
+                    }
+                    Some(Fault::Malformed) => Message::Text("{\"type\": \"res".to_owned()),
+                    Some(Fault::Drop) => break,
+                };
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+            _ = sink.close().await;
+        });
+
+        _ = outbox.send(text(json!({"type": "auth_required", "ha_version": "mock"})));
+        let mut authenticated = false;
+        let mut connection = None;
+
+        while let Some(Ok(message)) = stream.next().await {
+            let command: Value = match message {
+                Message::Text(data) => match serde_json::from_str(&data) {
+                    Ok(command) => command,
+                    Err(_) => continue,
+                },
+                Message::Close(_) => break,
+                _ => continue,
+            };
+
+            if !authenticated {
+                let token = command["access_token"].as_str().unwrap_or_default();
+                let accepted = match &self.lock().token {
+                    Some(expected) => expected == token,
+                    None => true,
+                };
+                if !accepted {
+                    _ = outbox.send(text(
+                        json!({"type": "auth_invalid", "message": "Invalid access token"}),
+                    ));
+                    break;
+                }
+                authenticated = true;
+                _ = outbox.send(text(json!({"type": "auth_ok", "ha_version": "mock"})));
+
+                let mut mock = self.lock();
+                mock.last_connection += 1;
+                let id = mock.last_connection;
+                mock.connections.insert(
+                    id,
+                    Connection {
+                        outbox: outbox.clone(),
+                        subscriptions: HashMap::new(),
+                    },
+                );
+                connection = Some(id);
+                continue;
+            }
+
+            if let Some(connection) = connection {
+                let reply = self.lock().handle(connection, &command);
+                _ = outbox.send(text(reply));
+            }
+        }
+
+        if let Some(connection) = connection {
+            self.lock().connections.remove(&connection);
+        }
+    }
+}
+
+impl MockState {
+    // builds the reply to the command, events caused by the command are sent first
+    fn handle(&mut self, connection: usize, command: &Value) -> Value {
+        let id = command["id"].clone();
+        let result =
+            |result: Value| json!({"id": id, "type": "result", "success": true, "result": result});
+
+        match command["type"].as_str().unwrap_or_default() {
+            "ping" => json!({"id": id, "type": "pong"}),
+            "supported_features" => result(Value::Null),
+            "get_states" => result(json!(self.entities.values().collect::<Vec<_>>())),
+            "get_services" => result(match &self.services {
+                Value::Null => json!({}),
+                services => services.clone(),
+            }),
+            "config/entity_registry/list" => result(Value::Array(
+                self.entities
+                    .keys()
+                    .map(|entity_id| json!({"entity_id": entity_id, "platform": "mock"}))
+                    .collect(),
+            )),
+            "config/device_registry/list" => result(json!([])),
+            "subscribe_events" => {
+                let event_type = command["event_type"].as_str().map(str::to_owned);
+                if let (Some(subscription), Some(connection)) = (
+                    command["id"].as_u64(),
+                    self.connections.get_mut(&connection),
+                ) {
+                    connection.subscriptions.insert(subscription, event_type);
+                }
+                result(Value::Null)
+            }
+            "unsubscribe_events" => {
+                let subscription = command["subscription"].as_u64().unwrap_or_default();
+                let removed = self
+                    .connections
+                    .get_mut(&connection)
+                    .and_then(|connection| connection.subscriptions.remove(&subscription));
+                match removed {
+                    Some(_) => result(Value::Null),
+                    None => error(&id, "not_found", "Subscription not found."),
+                }
+            }
+            "call_service" => {
+                let context = self.call_service(command);
+                result(json!({"context": context, "response": null}))
+            }
+            msg_type => match msg_type.strip_suffix("/create") {
+                Some(domain) => {
+                    let name = command["name"].as_str().unwrap_or_default();
+                    let object_id = name.to_lowercase().replace(' ', "_");
+                    let entity_id = format!("{}.{}", domain, object_id);
+                    let old_state = self.entities.get(&entity_id).cloned();
+                    let new_state = HaState {
+                        entity_id,
+                        attributes: Some(HashMap::from([(
+                            "friendly_name".to_owned(),
+                            json!(name),
+                        )])),
+                        state: initial_state(domain).to_owned(),
+                        ..Default::default()
+                    };
+                    self.change_state(old_state, new_state, None);
+                    result(json!({"id": object_id, "name": name}))
+                }
+                None => error(&id, "unknown_command", "Unknown command."),
+            },
+        }
+    }
+
+    // changes the targeted entities and returns the context of the call
+    fn call_service(&mut self, command: &Value) -> Value {
+        let domain = command["domain"].as_str().unwrap_or_default().to_owned();
+        let service = command["service"].as_str().unwrap_or_default().to_owned();
+        let service_data = command
+            .get("service_data")
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+        let target = command
+            .get("target")
+            .and_then(|target| target.get("entity_id"))
+            .or_else(|| service_data.get("entity_id"));
+        let entity_ids: Vec<String> = match target {
+            Some(Value::String(entity_id)) => vec![entity_id.clone()],
+            Some(Value::Array(entity_ids)) => entity_ids
+                .iter()
+                .filter_map(|entity_id| entity_id.as_str().map(str::to_owned))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        self.last_context += 1;
+        let context = json!({"id": format!("MOCK{:022}", self.last_context), "parent_id": null, "user_id": null});
+
+        for entity_id in &entity_ids {
+            let Some(old_state) = self.entities.get(entity_id).cloned() else {
+                continue;
+            };
+            if let Some(state) = next_state(&service, &old_state.state, &service_data) {
+                let mut new_state = old_state.clone();
+                new_state.state = state;
+                self.change_state(Some(old_state), new_state, Some(context.clone()));
+            }
+        }
+
+        self.calls.push(MockServiceCall {
+            domain,
+            service,
+            service_data,
+            entity_ids,
+        });
+        context
+    }
+
+    fn change_state(
+        &mut self,
+        old_state: Option<HaState>,
+        mut new_state: HaState,
+        context: Option<Value>,
+    ) {
+        let now = timestamp(SystemTime::now());
+        let changed = old_state.as_ref().map(|old| old.state != new_state.state);
+        new_state.last_updated = Some(now.clone());
+        new_state.last_changed = match (changed, &old_state) {
+            (Some(false), Some(old)) => old.last_changed.clone(),
+            _ => Some(now),
+        };
+        new_state.context = context.and_then(|context| serde_json::from_value(context).ok());
+
+        let data = json!({
+            "entity_id": new_state.entity_id,
+            "old_state": old_state,
+            "new_state": new_state,
+        });
+        self.entities.insert(new_state.entity_id.clone(), new_state);
+        self.fire("state_changed", data);
+    }
+
+    fn fire(&mut self, event_type: &str, data: Value) {
+        let time_fired = timestamp(SystemTime::now());
+        for connection in self.connections.values() {
+            for (subscription, subscribed) in &connection.subscriptions {
+                if subscribed
+                    .as_deref()
+                    .is_some_and(|subscribed| subscribed != event_type)
+                {
+                    continue;
+                }
+                let event = json!({
+                    "id": subscription,
+                    "type": "event",
+                    "event": {
+                        "event_type": event_type,
+                        "data": data,
+                        "origin": "LOCAL",
+                        "time_fired": time_fired,
+                    },
+                });
+                _ = connection.outbox.send(text(event));
+            }
+        }
+    }
+}
+
+/// The state after the service, `None` if the service does not change the state
+fn next_state(service: &str, state: &str, service_data: &Value) -> Option<String> {
+    let state = match service {
+        "turn_on" => "on",
+        "turn_off" => "off",
+        "toggle" if state == "on" => "off",
+        "toggle" => "on",
+        "lock" => "locked",
+        "unlock" => "unlocked",
+        "open_cover" => "open",
+        "close_cover" => "closed",
+        "set_value" => return service_data.get("value").map(value_to_state),
+        "select_option" => return service_data.get("option").map(value_to_state),
+        _ => return None,
+    };
+    Some(state.to_owned())
+}
+
+fn initial_state(domain: &str) -> &'static str {
+    match domain {
+        "input_boolean" => "off",
+        "input_number" => "0.0",
+        _ => "unknown",
+    }
+}
+
+fn value_to_state(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn error(id: &Value, code: &str, message: &str) -> Value {
+    json!({
+        "id": id,
+        "type": "result",
+        "success": false,
+        "error": {"code": code, "message": message},
+    })
+}
+
+fn text(message: Value) -> Message {
+    Message::Text(message.to_string())
+}
+
+// formats the time like Home Assistant, e.g. 2023-08-28T09:08:13.985677+00:00
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let days = (secs / 86_400) as i64;
+    let (hour, minute, second) = (secs % 86_400 / 3600, secs % 3600 / 60, secs % 60);
+
+    // civil date from days since the epoch, by Howard Hinnant
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}+00:00",
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        since_epoch.subsec_micros()
+    )
+}
@@ -0,0 +1,392 @@
+//! A client for the REST api of Home Assistant, with the `rest` feature
+//!
+//! Some data is only, or more easily, available over the REST api, like the
+//! history, the logbook, camera images and the error log. [`HaRestClient`]
+//! uses the same token sources and types as the websocket client.
+//!
+//! ```no_run
+//! # async fn example() -> r_hassclient::HassResult<()> {
+//! use r_hassclient::{auth::EnvToken, rest::HaRestClient};
+//!
+//! let base_url = url::Url::parse("http://localhost:8123")?;
+//! let rest = HaRestClient::new(base_url, EnvToken::new("HASS_TOKEN"));
+//! let history = rest
+//!     .get_history("2024-01-01T00:00:00Z", None, &["sensor.outside_temperature"])
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{collections::BTreeMap, sync::Arc};
+use url::Url;
+
+use crate::{
+    auth::TokenSource, config, services::ServiceCall, HaServices, HaState, HassConfig, HassError,
+    HassResult, ServiceDescription,
+};
+
+mod sensor;
+pub use sensor::{SensorPublisher, VirtualSensor};
+
+/// An entry of the logbook
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LogbookEntry {
+    pub when: String,
+    pub name: Option<String>,
+    pub message: Option<String>,
+    pub entity_id: Option<String>,
+    pub state: Option<String>,
+    pub domain: Option<String>,
+    pub context_user_id: Option<String>,
+}
+
+/// The result of checking the configuration of Home Assistant
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigCheck {
+    /// `valid` or `invalid`
+    pub result: String,
+    pub errors: Option<String>,
+}
+
+/// A domain and its services, as listed by `/api/services`
+#[derive(Deserialize)]
+struct DomainServices {
+    domain: String,
+    services: BTreeMap<String, ServiceDescription>,
+}
+
+/// A client for the REST api of Home Assistant
+///
+/// Every request is authenticated with a token of the source. When Home
+/// Assistant rejects the token, the source is told and the request is sent
+/// once more with a new token.
+#[derive(Clone)]
+pub struct HaRestClient {
+    http: reqwest::Client,
+    base_url: Url,
+    token_source: Arc<dyn TokenSource>,
+}
+
+impl HaRestClient {
+    /// Creates a client for Home Assistant at the base url, like `http://localhost:8123`
+    ///
+    /// A websocket url, like the one of the websocket client, is turned into
+    /// its base url.
+    pub fn new(base_url: Url, token_source: impl TokenSource + 'static) -> HaRestClient {
+        HaRestClient::with_token_source(base_url, Arc::new(token_source))
+    }
+
+    pub(crate) fn with_token_source(
+        base_url: Url,
+        token_source: Arc<dyn TokenSource>,
+    ) -> HaRestClient {
+        HaRestClient {
+            http: reqwest::Client::new(),
+            base_url: config::rest_url(&base_url),
+            token_source,
+        }
+    }
+
+    /// A client with the url and token source found in the environment
+    ///
+    /// See [`crate::client::HaClientBuilder::from_env`] for the configurations used.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no configuration is found or it is invalid.
+    pub fn from_env() -> HassResult<HaRestClient> {
+        let (url, token_source) = config::detect(|name| std::env::var(name).ok())?;
+        Ok(HaRestClient::with_token_source(url, token_source))
+    }
+
+    /// The base url of the REST api, like `http://localhost:8123/`
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// The configuration of Home Assistant, from `/api/config`
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn get_config(&self) -> HassResult<HassConfig> {
+        self.get_json("api/config").await
+    }
+
+    /// The states of all entities, from `/api/states`
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn get_states(&self) -> HassResult<Vec<HaState>> {
+        self.get_json("api/states").await
+    }
+
+    /// The state of the entity, `None` when Home Assistant does not know the entity
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn get_state(&self, entity_id: &str) -> HassResult<Option<HaState>> {
+        match self.get_json(&format!("api/states/{}", entity_id)).await {
+            Ok(state) => Ok(Some(state)),
+            Err(HassError::HttpError(404, _)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Creates or updates the state of the entity, returns the new state
+    ///
+    /// The entity does not need to belong to an integration, like a sensor
+    /// computed outside of Home Assistant. Such states are not restored when
+    /// Home Assistant restarts, see [`SensorPublisher`] to publish them again.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn set_state(
+        &self,
+        entity_id: &str,
+        state: &str,
+        attributes: Map<String, Value>,
+    ) -> HassResult<HaState> {
+        let body = json!({ "state": state, "attributes": attributes });
+        let path = format!("api/states/{}", entity_id);
+        let response = self
+            .send(Method::POST, &path, |request| request.json(&body))
+            .await?;
+        json(response).await
+    }
+
+    /// Removes the state of the entity, returns `false` when there was none
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn delete_state(&self, entity_id: &str) -> HassResult<bool> {
+        let path = format!("api/states/{}", entity_id);
+        match self.send(Method::DELETE, &path, |request| request).await {
+            Ok(_) => Ok(true),
+            Err(HassError::HttpError(404, _)) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// The services of all domains, from `/api/services`
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn get_services(&self) -> HassResult<HaServices> {
+        let domains: Vec<DomainServices> = self.get_json("api/services").await?;
+        Ok(domains
+            .into_iter()
+            .map(|domain| (domain.domain, domain.services))
+            .collect())
+    }
+
+    /// Calls the service, returns the states changed by the call
+    ///
+    /// The targets are sent with the service data, like the REST api expects.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn call_service(
+        &self,
+        service_call: impl Into<ServiceCall>,
+    ) -> HassResult<Vec<HaState>> {
+        let service_call = service_call.into();
+        let mut data = service_call.data;
+        // a target only holds lists of strings, which always serialize to an object
+        if let Some(Value::Object(target)) = service_call
+            .target
+            .map(|target| serde_json::to_value(target).expect("a target serializes to json"))
+        {
+            data.extend(target);
+        }
+        let path = format!(
+            "api/services/{}/{}",
+            service_call.domain, service_call.service
+        );
+        let response = self
+            .send(Method::POST, &path, |request| request.json(&data))
+            .await?;
+        json(response).await
+    }
+
+    /// Fires an event, returns the message of Home Assistant
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn fire_event(&self, event_type: &str, data: Option<Value>) -> HassResult<String> {
+        let data = data.unwrap_or_else(|| Value::Object(Map::new()));
+        let path = format!("api/events/{}", event_type);
+        let response = self
+            .send(Method::POST, &path, |request| request.json(&data))
+            .await?;
+        let message: Value = json(response).await?;
+        Ok(message["message"].as_str().unwrap_or_default().to_owned())
+    }
+
+    /// The state changes since the start, one list of states per entity
+    ///
+    /// The timestamps are ISO 8601, like `2024-01-01T00:00:00+00:00`. Without
+    /// an end the history of one day is returned, without entity ids the
+    /// history of all entities.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn get_history(
+        &self,
+        start: &str,
+        end: Option<&str>,
+        entity_ids: &[&str],
+    ) -> HassResult<Vec<Vec<HaState>>> {
+        let mut query = Vec::new();
+        if !entity_ids.is_empty() {
+            query.push(("filter_entity_id", entity_ids.join(",")));
+        }
+        if let Some(end) = end {
+            query.push(("end_time", end.to_owned()));
+        }
+        let path = format!("api/history/period/{}", start);
+        let response = self
+            .send(Method::GET, &path, |request| request.query(&query))
+            .await?;
+        json(response).await
+    }
+
+    /// The logbook entries since the start, optionally of one entity
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn get_logbook(
+        &self,
+        start: &str,
+        end: Option<&str>,
+        entity_id: Option<&str>,
+    ) -> HassResult<Vec<LogbookEntry>> {
+        let mut query = Vec::new();
+        if let Some(entity_id) = entity_id {
+            query.push(("entity", entity_id));
+        }
+        if let Some(end) = end {
+            query.push(("end_time", end));
+        }
+        let path = format!("api/logbook/{}", start);
+        let response = self
+            .send(Method::GET, &path, |request| request.query(&query))
+            .await?;
+        json(response).await
+    }
+
+    /// Renders the template, like `{{ states('sun.sun') }}`
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the template is invalid or the request fails.
+    pub async fn render_template(&self, template: &str) -> HassResult<String> {
+        let body = json!({ "template": template });
+        let response = self
+            .send(Method::POST, "api/template", |request| request.json(&body))
+            .await?;
+        text(response).await
+    }
+
+    /// The error log of Home Assistant
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn get_error_log(&self) -> HassResult<String> {
+        let response = self
+            .send(Method::GET, "api/error_log", |request| request)
+            .await?;
+        text(response).await
+    }
+
+    /// The current image of the camera
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn get_camera_image(&self, entity_id: &str) -> HassResult<Vec<u8>> {
+        let path = format!("api/camera_proxy/{}", entity_id);
+        let response = self.send(Method::GET, &path, |request| request).await?;
+        let image = response.bytes().await.map_err(http_error)?;
+        Ok(image.to_vec())
+    }
+
+    /// Checks the configuration files of Home Assistant
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn check_config(&self) -> HassResult<ConfigCheck> {
+        let response = self
+            .send(Method::POST, "api/config/core/check_config", |request| {
+                request
+            })
+            .await?;
+        json(response).await
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> HassResult<T> {
+        let response = self.send(Method::GET, path, |request| request).await?;
+        json(response).await
+    }
+
+    /// Sends the request with a token, and once more with a new token when it is rejected
+    pub(crate) async fn send<F>(&self, method: Method, path: &str, build: F) -> HassResult<Response>
+    where
+        F: Fn(RequestBuilder) -> RequestBuilder,
+    {
+        let url = self.base_url.join(path)?;
+        let mut rejected = false;
+        loop {
+            let token = self.token_source.token().await?;
+            let request = self
+                .http
+                .request(method.clone(), url.clone())
+                .bearer_auth(token.expose());
+            let response = build(request).send().await.map_err(http_error)?;
+            match response.status() {
+                StatusCode::UNAUTHORIZED if !rejected => {
+                    self.token_source.rejected().await;
+                    rejected = true;
+                }
+                StatusCode::UNAUTHORIZED => {
+                    return Err(HassError::AuthenticationFailed(
+                        "the REST api rejected the token".to_owned(),
+                    ))
+                }
+                status if !status.is_success() => {
+                    let message = response.text().await.unwrap_or_default();
+                    return Err(HassError::HttpError(status.as_u16(), message));
+                }
+                _ => return Ok(response),
+            }
+        }
+    }
+}
+
+async fn json<T: DeserializeOwned>(response: Response) -> HassResult<T> {
+    let body = response.bytes().await.map_err(http_error)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+async fn text(response: Response) -> HassResult<String> {
+    response.text().await.map_err(http_error)
+}
+
+fn http_error(error: reqwest::Error) -> HassError {
+    HassError::GenericError(format!("REST request failed: {}", error))
+}
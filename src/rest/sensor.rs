@@ -0,0 +1,220 @@
+use serde_json::{Map, Value};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::{sync::Mutex, task::JoinHandle};
+
+use super::HaRestClient;
+use crate::{client::HaConnection, HaState, HassResult};
+
+/// A state computed outside of Home Assistant, published as an entity
+///
+/// ```
+/// use r_hassclient::rest::VirtualSensor;
+///
+/// let sensor = VirtualSensor::new("sensor.heat_pump_cop", 3.4)
+///     .friendly_name("Heat pump COP")
+///     .device_class("power_factor")
+///     .attribute("source", "r-hassclient");
+/// assert_eq!(sensor.state(), "3.4");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualSensor {
+    entity_id: String,
+    state: String,
+    attributes: Map<String, Value>,
+}
+
+impl VirtualSensor {
+    pub fn new(entity_id: &str, state: impl ToString) -> VirtualSensor {
+        VirtualSensor {
+            entity_id: entity_id.to_owned(),
+            state: state.to_string(),
+            attributes: Map::new(),
+        }
+    }
+
+    pub fn friendly_name(self, name: &str) -> VirtualSensor {
+        self.attribute("friendly_name", name)
+    }
+
+    pub fn unit_of_measurement(self, unit: &str) -> VirtualSensor {
+        self.attribute("unit_of_measurement", unit)
+    }
+
+    pub fn device_class(self, device_class: &str) -> VirtualSensor {
+        self.attribute("device_class", device_class)
+    }
+
+    pub fn icon(self, icon: &str) -> VirtualSensor {
+        self.attribute("icon", icon)
+    }
+
+    /// Adds an attribute, like `state_class` or a custom one
+    pub fn attribute(mut self, key: &str, value: impl Into<Value>) -> VirtualSensor {
+        self.attributes.insert(key.to_owned(), value.into());
+        self
+    }
+
+    /// The same sensor with a new state, the attributes are kept
+    pub fn with_state(mut self, state: impl ToString) -> VirtualSensor {
+        self.state = state.to_string();
+        self
+    }
+
+    pub fn entity_id(&self) -> &str {
+        &self.entity_id
+    }
+
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+
+    pub fn attributes(&self) -> &Map<String, Value> {
+        &self.attributes
+    }
+}
+
+/// Publishes virtual sensors and publishes them again when Home Assistant lost them
+///
+/// Home Assistant does not keep states set over the REST api when it
+/// restarts. The publisher remembers the last published state of every
+/// sensor, and publishes all of them again on [`SensorPublisher::republish`],
+/// periodically, or with [`SensorPublisher::republish_on_start`] on every new
+/// connection, since a restart always drops the websocket connection.
+///
+/// ```no_run
+/// # async fn example(conn: &mut r_hassclient::client::HaConnection, rest: r_hassclient::HaRestClient) -> r_hassclient::HassResult<()> {
+/// use r_hassclient::rest::{SensorPublisher, VirtualSensor};
+/// use std::time::Duration;
+///
+/// let publisher = SensorPublisher::new(rest);
+/// // after every (re)connect and authentication
+/// publisher.republish_on_start(conn).await?;
+/// publisher.republish_every(Duration::from_secs(300));
+///
+/// let sensor = VirtualSensor::new("sensor.outside_dew_point", 8.2)
+///     .friendly_name("Dew point")
+///     .unit_of_measurement("°C")
+///     .device_class("temperature");
+/// publisher.publish(sensor).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SensorPublisher {
+    rest: HaRestClient,
+    // held across the requests, so a republish never sends a state older than a publish
+    sensors: Arc<Mutex<BTreeMap<String, VirtualSensor>>>,
+}
+
+impl SensorPublisher {
+    pub fn new(rest: HaRestClient) -> SensorPublisher {
+        SensorPublisher {
+            rest,
+            sensors: Arc::default(),
+        }
+    }
+
+    /// Sets the state of the sensor in Home Assistant and remembers it
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails, the sensor
+    /// is still remembered and published on the next republish.
+    pub async fn publish(&self, sensor: VirtualSensor) -> HassResult<HaState> {
+        let mut sensors = self.sensors.lock().await;
+        sensors.insert(sensor.entity_id.clone(), sensor.clone());
+        self.set_state(&sensor).await
+    }
+
+    /// Removes the sensor from Home Assistant and forgets it
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request fails.
+    pub async fn remove(&self, entity_id: &str) -> HassResult<bool> {
+        let mut sensors = self.sensors.lock().await;
+        sensors.remove(entity_id);
+        self.rest.delete_state(entity_id).await
+    }
+
+    /// The last published sensors
+    pub async fn sensors(&self) -> Vec<VirtualSensor> {
+        self.sensors.lock().await.values().cloned().collect()
+    }
+
+    /// Publishes the last state of every sensor again
+    ///
+    /// Publishing and removing sensors waits until the republish is done.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the first error, after trying every sensor.
+    pub async fn republish(&self) -> HassResult<()> {
+        let sensors = self.sensors.lock().await;
+        let mut result = Ok(());
+        for sensor in sensors.values() {
+            if let Err(err) = self.set_state(sensor).await {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+        result
+    }
+
+    /// Republishes every period on a task, until the task is aborted
+    ///
+    /// Failures are reported and retried on the next period.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the period is zero.
+    pub fn republish_every(&self, period: Duration) -> JoinHandle<()> {
+        // checked here, the interval would only panic inside the task
+        assert!(!period.is_zero(), "the republish period must not be zero");
+        let publisher = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            // the first tick completes immediately, the sensors were just published
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(err) = publisher.republish().await {
+                    eprintln!("Failed to republish the virtual sensors: {}", err);
+                }
+            }
+        })
+    }
+
+    /// Republishes now, and when Home Assistant fires `homeassistant_started` on the connection
+    ///
+    /// Call this with every new connection, once it is authenticated. Home
+    /// Assistant may have restarted while the client was disconnected, and a
+    /// client connecting while Home Assistant starts gets `homeassistant_started`
+    /// once the integrations are loaded. Returns the id of the callback, used
+    /// to unsubscribe.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if republishing fails, then nothing
+    /// is subscribed, or if Home Assistant rejects the subscription.
+    pub async fn republish_on_start(&self, conn: &mut HaConnection) -> HassResult<u64> {
+        self.republish().await?;
+        let publisher = self.clone();
+        conn.subscribe_async("homeassistant_started", move |_| {
+            let publisher = publisher.clone();
+            async move {
+                if let Err(err) = publisher.republish().await {
+                    eprintln!("Failed to republish the virtual sensors: {}", err);
+                }
+            }
+        })
+        .await
+    }
+
+    async fn set_state(&self, sensor: &VirtualSensor) -> HassResult<HaState> {
+        self.rest
+            .set_state(&sensor.entity_id, &sensor.state, sensor.attributes.clone())
+            .await
+    }
+}
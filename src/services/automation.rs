@@ -0,0 +1,27 @@
+//! Services of the `automation` domain
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "automation";
+
+pub fn turn_on(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_on").target(target)
+}
+
+pub fn turn_off(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_off").target(target)
+}
+
+pub fn toggle(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "toggle").target(target)
+}
+
+/// Runs the actions of the automation, optionally skipping the conditions
+pub fn trigger(target: impl Into<Target>, skip_condition: bool) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "trigger")
+        .target(target)
+        .data("skip_condition", skip_condition)
+}
+
+pub fn reload() -> ServiceCall {
+    ServiceCall::new(DOMAIN, "reload")
+}
@@ -0,0 +1,73 @@
+//! Services of the `climate` domain
+use super::{ServiceCall, Target};
+use crate::HvacMode;
+
+const DOMAIN: &str = "climate";
+
+/// Sets the target temperature, see [`SetTemperature`] for the options
+pub fn set_temperature(target: impl Into<Target>) -> SetTemperature {
+    SetTemperature(ServiceCall::new(DOMAIN, "set_temperature").target(target))
+}
+
+pub fn set_hvac_mode(target: impl Into<Target>, hvac_mode: HvacMode) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "set_hvac_mode")
+        .target(target)
+        .data("hvac_mode", hvac_mode.as_str())
+}
+
+pub fn set_preset_mode(target: impl Into<Target>, preset_mode: &str) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "set_preset_mode")
+        .target(target)
+        .data("preset_mode", preset_mode)
+}
+
+pub fn set_fan_mode(target: impl Into<Target>, fan_mode: &str) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "set_fan_mode")
+        .target(target)
+        .data("fan_mode", fan_mode)
+}
+
+pub fn set_humidity(target: impl Into<Target>, humidity: u8) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "set_humidity")
+        .target(target)
+        .data("humidity", humidity)
+}
+
+pub fn turn_on(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_on").target(target)
+}
+
+pub fn turn_off(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_off").target(target)
+}
+
+/// Options for the `climate.set_temperature` service
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetTemperature(ServiceCall);
+
+impl SetTemperature {
+    /// The target temperature for devices with a single setpoint
+    pub fn temperature(self, temperature: f64) -> SetTemperature {
+        SetTemperature(self.0.data("temperature", temperature))
+    }
+
+    /// The target range for devices in `heat_cool` mode
+    pub fn target_temp_range(self, low: f64, high: f64) -> SetTemperature {
+        SetTemperature(
+            self.0
+                .data("target_temp_low", low)
+                .data("target_temp_high", high),
+        )
+    }
+
+    /// Changes the hvac mode at the same time as the temperature
+    pub fn hvac_mode(self, hvac_mode: HvacMode) -> SetTemperature {
+        SetTemperature(self.0.data("hvac_mode", hvac_mode.as_str()))
+    }
+}
+
+impl From<SetTemperature> for ServiceCall {
+    fn from(set_temperature: SetTemperature) -> Self {
+        set_temperature.0
+    }
+}
@@ -0,0 +1,46 @@
+//! Services of the `cover` domain
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "cover";
+
+pub fn open(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "open_cover").target(target)
+}
+
+pub fn close(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "close_cover").target(target)
+}
+
+pub fn stop(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "stop_cover").target(target)
+}
+
+pub fn toggle(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "toggle").target(target)
+}
+
+/// Moves the cover to a position between 0 (closed) and 100 (open)
+pub fn set_position(target: impl Into<Target>, position: u8) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "set_cover_position")
+        .target(target)
+        .data("position", position.min(100))
+}
+
+pub fn open_tilt(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "open_cover_tilt").target(target)
+}
+
+pub fn close_tilt(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "close_cover_tilt").target(target)
+}
+
+pub fn stop_tilt(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "stop_cover_tilt").target(target)
+}
+
+/// Moves the tilt to a position between 0 (closed) and 100 (open)
+pub fn set_tilt_position(target: impl Into<Target>, tilt_position: u8) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "set_cover_tilt_position")
+        .target(target)
+        .data("tilt_position", tilt_position.min(100))
+}
@@ -0,0 +1,72 @@
+//! Services of the `fan` domain
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "fan";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Reverse,
+}
+
+/// Turns the fan on, see [`TurnOn`] for the options
+pub fn turn_on(target: impl Into<Target>) -> TurnOn {
+    TurnOn(ServiceCall::new(DOMAIN, "turn_on").target(target))
+}
+
+pub fn turn_off(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_off").target(target)
+}
+
+pub fn toggle(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "toggle").target(target)
+}
+
+/// Sets the speed in percent between 0 and 100
+pub fn set_percentage(target: impl Into<Target>, percentage: u8) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "set_percentage")
+        .target(target)
+        .data("percentage", percentage.min(100))
+}
+
+pub fn set_preset_mode(target: impl Into<Target>, preset_mode: &str) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "set_preset_mode")
+        .target(target)
+        .data("preset_mode", preset_mode)
+}
+
+pub fn oscillate(target: impl Into<Target>, oscillating: bool) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "oscillate")
+        .target(target)
+        .data("oscillating", oscillating)
+}
+
+pub fn set_direction(target: impl Into<Target>, direction: Direction) -> ServiceCall {
+    let direction = match direction {
+        Direction::Forward => "forward",
+        Direction::Reverse => "reverse",
+    };
+    ServiceCall::new(DOMAIN, "set_direction")
+        .target(target)
+        .data("direction", direction)
+}
+
+/// Options for the `fan.turn_on` service
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnOn(ServiceCall);
+
+impl TurnOn {
+    pub fn percentage(self, percentage: u8) -> TurnOn {
+        TurnOn(self.0.data("percentage", percentage.min(100)))
+    }
+
+    pub fn preset_mode(self, preset_mode: &str) -> TurnOn {
+        TurnOn(self.0.data("preset_mode", preset_mode))
+    }
+}
+
+impl From<TurnOn> for ServiceCall {
+    fn from(turn_on: TurnOn) -> Self {
+        turn_on.0
+    }
+}
@@ -0,0 +1,37 @@
+//! Services of the `homeassistant` domain, they work on entities of any domain
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "homeassistant";
+
+pub fn turn_on(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_on").target(target)
+}
+
+pub fn turn_off(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_off").target(target)
+}
+
+pub fn toggle(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "toggle").target(target)
+}
+
+/// Forces the entities to update their state
+pub fn update_entity(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "update_entity").target(target)
+}
+
+pub fn check_config() -> ServiceCall {
+    ServiceCall::new(DOMAIN, "check_config")
+}
+
+pub fn reload_core_config() -> ServiceCall {
+    ServiceCall::new(DOMAIN, "reload_core_config")
+}
+
+pub fn reload_all() -> ServiceCall {
+    ServiceCall::new(DOMAIN, "reload_all")
+}
+
+pub fn restart() -> ServiceCall {
+    ServiceCall::new(DOMAIN, "restart")
+}
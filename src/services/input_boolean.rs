@@ -0,0 +1,16 @@
+//! Services of the `input_boolean` helper
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "input_boolean";
+
+pub fn turn_on(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_on").target(target)
+}
+
+pub fn turn_off(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_off").target(target)
+}
+
+pub fn toggle(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "toggle").target(target)
+}
@@ -0,0 +1,6 @@
+//! Services of the `input_button` helper
+use super::{ServiceCall, Target};
+
+pub fn press(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new("input_button", "press").target(target)
+}
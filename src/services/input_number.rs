@@ -0,0 +1,20 @@
+//! Services of the `input_number` helper
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "input_number";
+
+pub fn set_value(target: impl Into<Target>, value: f64) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "set_value")
+        .target(target)
+        .data("value", value)
+}
+
+/// Increments the value by one step
+pub fn increment(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "increment").target(target)
+}
+
+/// Decrements the value by one step
+pub fn decrement(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "decrement").target(target)
+}
@@ -0,0 +1,29 @@
+//! Services of the `input_select` helper
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "input_select";
+
+pub fn select_option(target: impl Into<Target>, option: &str) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "select_option")
+        .target(target)
+        .data("option", option)
+}
+
+pub fn select_next(target: impl Into<Target>, cycle: bool) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "select_next")
+        .target(target)
+        .data("cycle", cycle)
+}
+
+pub fn select_previous(target: impl Into<Target>, cycle: bool) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "select_previous")
+        .target(target)
+        .data("cycle", cycle)
+}
+
+/// Replaces the options of the helper
+pub fn set_options(target: impl Into<Target>, options: &[&str]) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "set_options")
+        .target(target)
+        .data("options", options.to_vec())
+}
@@ -0,0 +1,8 @@
+//! Services of the `input_text` helper
+use super::{ServiceCall, Target};
+
+pub fn set_value(target: impl Into<Target>, value: &str) -> ServiceCall {
+    ServiceCall::new("input_text", "set_value")
+        .target(target)
+        .data("value", value)
+}
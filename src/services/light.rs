@@ -0,0 +1,120 @@
+//! Services of the `light` domain
+use std::time::Duration;
+
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "light";
+
+/// Flash the light, a short or long flash is supported by most lights
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flash {
+    Short,
+    Long,
+}
+
+impl Flash {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Flash::Short => "short",
+            Flash::Long => "long",
+        }
+    }
+}
+
+/// Turns the light on, see [`TurnOn`] for the options
+pub fn turn_on(target: impl Into<Target>) -> TurnOn {
+    TurnOn(ServiceCall::new(DOMAIN, "turn_on").target(target))
+}
+
+/// Toggles the light, the options of [`TurnOn`] are used if the light is turned on
+pub fn toggle(target: impl Into<Target>) -> TurnOn {
+    TurnOn(ServiceCall::new(DOMAIN, "toggle").target(target))
+}
+
+pub fn turn_off(target: impl Into<Target>) -> TurnOff {
+    TurnOff(ServiceCall::new(DOMAIN, "turn_off").target(target))
+}
+
+/// Options for the `light.turn_on` and `light.toggle` services
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnOn(ServiceCall);
+
+impl TurnOn {
+    /// Brightness between 0 and 255
+    pub fn brightness(self, brightness: u8) -> TurnOn {
+        TurnOn(self.0.data("brightness", brightness))
+    }
+
+    /// Brightness in percent between 0 and 100
+    pub fn brightness_pct(self, brightness_pct: u8) -> TurnOn {
+        TurnOn(self.0.data("brightness_pct", brightness_pct.min(100)))
+    }
+
+    /// Changes the brightness relative to the current value, between -100 and 100
+    pub fn brightness_step_pct(self, step_pct: i8) -> TurnOn {
+        TurnOn(
+            self.0
+                .data("brightness_step_pct", step_pct.clamp(-100, 100)),
+        )
+    }
+
+    pub fn transition(self, transition: Duration) -> TurnOn {
+        TurnOn(self.0.data("transition", transition.as_secs_f64()))
+    }
+
+    pub fn color_temp_kelvin(self, kelvin: u32) -> TurnOn {
+        TurnOn(self.0.data("color_temp_kelvin", kelvin))
+    }
+
+    pub fn rgb_color(self, red: u8, green: u8, blue: u8) -> TurnOn {
+        TurnOn(self.0.data("rgb_color", vec![red, green, blue]))
+    }
+
+    /// Hue between 0 and 360 and saturation between 0 and 100
+    pub fn hs_color(self, hue: f32, saturation: f32) -> TurnOn {
+        TurnOn(self.0.data("hs_color", vec![hue, saturation]))
+    }
+
+    pub fn xy_color(self, x: f32, y: f32) -> TurnOn {
+        TurnOn(self.0.data("xy_color", vec![x, y]))
+    }
+
+    /// A human readable color name, like `red` or `dodgerblue`
+    pub fn color_name(self, color_name: &str) -> TurnOn {
+        TurnOn(self.0.data("color_name", color_name))
+    }
+
+    pub fn effect(self, effect: &str) -> TurnOn {
+        TurnOn(self.0.data("effect", effect))
+    }
+
+    pub fn flash(self, flash: Flash) -> TurnOn {
+        TurnOn(self.0.data("flash", flash.as_str()))
+    }
+}
+
+impl From<TurnOn> for ServiceCall {
+    fn from(turn_on: TurnOn) -> Self {
+        turn_on.0
+    }
+}
+
+/// Options for the `light.turn_off` service
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnOff(ServiceCall);
+
+impl TurnOff {
+    pub fn transition(self, transition: Duration) -> TurnOff {
+        TurnOff(self.0.data("transition", transition.as_secs_f64()))
+    }
+
+    pub fn flash(self, flash: Flash) -> TurnOff {
+        TurnOff(self.0.data("flash", flash.as_str()))
+    }
+}
+
+impl From<TurnOff> for ServiceCall {
+    fn from(turn_off: TurnOff) -> Self {
+        turn_off.0
+    }
+}
@@ -0,0 +1,34 @@
+//! Services of the `lock` domain
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "lock";
+
+pub fn lock(target: impl Into<Target>) -> LockCall {
+    LockCall(ServiceCall::new(DOMAIN, "lock").target(target))
+}
+
+pub fn unlock(target: impl Into<Target>) -> LockCall {
+    LockCall(ServiceCall::new(DOMAIN, "unlock").target(target))
+}
+
+/// Opens the latch of the lock, if supported
+pub fn open(target: impl Into<Target>) -> LockCall {
+    LockCall(ServiceCall::new(DOMAIN, "open").target(target))
+}
+
+/// Options for the `lock` services
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockCall(ServiceCall);
+
+impl LockCall {
+    /// The code needed by locks that are protected by a code
+    pub fn code(self, code: &str) -> LockCall {
+        LockCall(self.0.data("code", code))
+    }
+}
+
+impl From<LockCall> for ServiceCall {
+    fn from(lock_call: LockCall) -> Self {
+        lock_call.0
+    }
+}
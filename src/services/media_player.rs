@@ -0,0 +1,114 @@
+//! Services of the `media_player` domain
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "media_player";
+
+/// How the media is added to the playlist of the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Enqueue {
+    /// Play now, keep the rest of the queue
+    Play,
+    /// Play after the current item
+    Next,
+    /// Add to the end of the queue
+    Add,
+    /// Play now and clear the queue
+    Replace,
+}
+
+impl Enqueue {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Enqueue::Play => "play",
+            Enqueue::Next => "next",
+            Enqueue::Add => "add",
+            Enqueue::Replace => "replace",
+        }
+    }
+}
+
+/// Plays media, see [`PlayMedia`] for the options
+pub fn play_media(target: impl Into<Target>, content_id: &str, content_type: &str) -> PlayMedia {
+    PlayMedia(
+        ServiceCall::new(DOMAIN, "play_media")
+            .target(target)
+            .data("media_content_id", content_id)
+            .data("media_content_type", content_type),
+    )
+}
+
+pub fn turn_on(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_on").target(target)
+}
+
+pub fn turn_off(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_off").target(target)
+}
+
+pub fn toggle(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "toggle").target(target)
+}
+
+pub fn media_play(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "media_play").target(target)
+}
+
+pub fn media_pause(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "media_pause").target(target)
+}
+
+pub fn media_play_pause(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "media_play_pause").target(target)
+}
+
+pub fn media_stop(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "media_stop").target(target)
+}
+
+pub fn media_next_track(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "media_next_track").target(target)
+}
+
+pub fn media_previous_track(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "media_previous_track").target(target)
+}
+
+/// Sets the volume between 0.0 and 1.0
+pub fn volume_set(target: impl Into<Target>, volume_level: f64) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "volume_set")
+        .target(target)
+        .data("volume_level", volume_level.clamp(0.0, 1.0))
+}
+
+pub fn volume_mute(target: impl Into<Target>, is_volume_muted: bool) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "volume_mute")
+        .target(target)
+        .data("is_volume_muted", is_volume_muted)
+}
+
+pub fn select_source(target: impl Into<Target>, source: &str) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "select_source")
+        .target(target)
+        .data("source", source)
+}
+
+/// Options for the `media_player.play_media` service
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayMedia(ServiceCall);
+
+impl PlayMedia {
+    pub fn enqueue(self, enqueue: Enqueue) -> PlayMedia {
+        PlayMedia(self.0.data("enqueue", enqueue.as_str()))
+    }
+
+    /// Plays the media as an announcement, pausing and resuming the current media
+    pub fn announce(self, announce: bool) -> PlayMedia {
+        PlayMedia(self.0.data("announce", announce))
+    }
+}
+
+impl From<PlayMedia> for ServiceCall {
+    fn from(play_media: PlayMedia) -> Self {
+        play_media.0
+    }
+}
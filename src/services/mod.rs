@@ -0,0 +1,190 @@
+//! Typed builders for calling services in Home Assistant
+//!
+//! Every builder compiles down to a [`ServiceCall`] that is sent with
+//! [`HaConnection::call`](crate::client::HaConnection::call).
+//!
+//! ```no_run
+//! # async fn example(conn: &mut r_hassclient::client::HaConnection) -> r_hassclient::HassResult<()> {
+//! use std::time::Duration;
+//! use r_hassclient::services::light;
+//!
+//! conn.call(
+//!     light::turn_on("light.kitchen")
+//!         .brightness_pct(40)
+//!         .transition(Duration::from_secs(2)),
+//! )
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::CallService;
+
+pub mod automation;
+pub mod climate;
+pub mod cover;
+pub mod fan;
+pub mod homeassistant;
+pub mod input_boolean;
+pub mod input_button;
+pub mod input_number;
+pub mod input_select;
+pub mod input_text;
+pub mod light;
+pub mod lock;
+pub mod media_player;
+pub mod notify;
+pub mod scene;
+pub mod script;
+pub mod switch;
+
+#[cfg(test)]
+mod tests;
+
+/// A service call that is not yet sent to Home Assistant
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceCall {
+    pub domain: String,
+    pub service: String,
+    pub target: Option<Target>,
+    pub data: Map<String, Value>,
+}
+
+impl ServiceCall {
+    pub fn new(domain: &str, service: &str) -> ServiceCall {
+        ServiceCall {
+            domain: domain.to_owned(),
+            service: service.to_owned(),
+            target: None,
+            data: Map::new(),
+        }
+    }
+
+    /// Sets the entities, devices, areas or labels the service is called on
+    pub fn target(mut self, target: impl Into<Target>) -> ServiceCall {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Adds a field to the service data
+    pub fn data(mut self, key: &str, value: impl Into<Value>) -> ServiceCall {
+        self.data.insert(key.to_owned(), value.into());
+        self
+    }
+}
+
+impl From<ServiceCall> for CallService {
+    fn from(service_call: ServiceCall) -> Self {
+        CallService {
+            id: None,
+            msg_type: "call_service".to_owned(),
+            domain: service_call.domain,
+            service: service_call.service,
+            service_data: (!service_call.data.is_empty())
+                .then_some(Value::Object(service_call.data)),
+            // a target only holds lists of strings, which always serialize
+            target: service_call
+                .target
+                .map(|target| serde_json::to_value(target).expect("a target serializes to json")),
+        }
+    }
+}
+
+/// The target of a service call
+///
+/// Any combination of entities, devices, areas and labels can be targeted. A
+/// single `&str` or `String` converts to a target of one entity.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct Target {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub entity_id: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub device_id: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub area_id: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub label_id: Vec<String>,
+}
+
+impl Target {
+    pub fn entity(entity_id: &str) -> Target {
+        Target::default().and_entity(entity_id)
+    }
+
+    pub fn device(device_id: &str) -> Target {
+        Target::default().and_device(device_id)
+    }
+
+    pub fn area(area_id: &str) -> Target {
+        Target::default().and_area(area_id)
+    }
+
+    pub fn label(label_id: &str) -> Target {
+        Target::default().and_label(label_id)
+    }
+
+    pub fn and_entity(mut self, entity_id: &str) -> Target {
+        self.entity_id.push(entity_id.to_owned());
+        self
+    }
+
+    pub fn and_device(mut self, device_id: &str) -> Target {
+        self.device_id.push(device_id.to_owned());
+        self
+    }
+
+    pub fn and_area(mut self, area_id: &str) -> Target {
+        self.area_id.push(area_id.to_owned());
+        self
+    }
+
+    pub fn and_label(mut self, label_id: &str) -> Target {
+        self.label_id.push(label_id.to_owned());
+        self
+    }
+}
+
+impl From<&str> for Target {
+    fn from(entity_id: &str) -> Self {
+        Target::entity(entity_id)
+    }
+}
+
+impl From<String> for Target {
+    fn from(entity_id: String) -> Self {
+        Target::entity(&entity_id)
+    }
+}
+
+impl From<&String> for Target {
+    fn from(entity_id: &String) -> Self {
+        Target::entity(entity_id)
+    }
+}
+
+impl<const N: usize> From<[&str; N]> for Target {
+    fn from(entity_ids: [&str; N]) -> Self {
+        entity_ids
+            .into_iter()
+            .fold(Target::default(), Target::and_entity)
+    }
+}
+
+impl From<Vec<&str>> for Target {
+    fn from(entity_ids: Vec<&str>) -> Self {
+        entity_ids
+            .into_iter()
+            .fold(Target::default(), Target::and_entity)
+    }
+}
+
+impl From<Vec<String>> for Target {
+    fn from(entity_ids: Vec<String>) -> Self {
+        Target {
+            entity_id: entity_ids,
+            ..Default::default()
+        }
+    }
+}
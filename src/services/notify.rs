@@ -0,0 +1,54 @@
+//! Services of the `notify` domain
+use serde_json::Value;
+
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "notify";
+
+/// Sends a notification with a notify service, i.e. `mobile_app_my_phone` for
+/// `notify.mobile_app_my_phone`. See [`Notification`] for the options.
+pub fn send(service: &str, message: &str) -> Notification {
+    Notification(ServiceCall::new(DOMAIN, service).data("message", message))
+}
+
+/// Sends a message to notify entities
+pub fn send_message(target: impl Into<Target>, message: &str) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "send_message")
+        .target(target)
+        .data("message", message)
+}
+
+/// Creates a persistent notification in the Home Assistant UI
+pub fn persistent_notification(message: &str, title: Option<&str>) -> ServiceCall {
+    let call = ServiceCall::new(DOMAIN, "persistent_notification").data("message", message);
+    match title {
+        Some(title) => call.data("title", title),
+        None => call,
+    }
+}
+
+/// Options for the legacy `notify.<service>` services
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification(ServiceCall);
+
+impl Notification {
+    pub fn title(self, title: &str) -> Notification {
+        Notification(self.0.data("title", title))
+    }
+
+    /// The recipients of the notification, the meaning depends on the platform
+    pub fn recipients(self, recipients: &[&str]) -> Notification {
+        Notification(self.0.data("target", recipients.to_vec()))
+    }
+
+    /// Platform specific data, like actions or images for the mobile app
+    pub fn data(self, data: Value) -> Notification {
+        Notification(self.0.data("data", data))
+    }
+}
+
+impl From<Notification> for ServiceCall {
+    fn from(notification: Notification) -> Self {
+        notification.0
+    }
+}
@@ -0,0 +1,14 @@
+//! Services of the `scene` domain
+use std::time::Duration;
+
+use super::{ServiceCall, Target};
+
+/// Activates the scene
+pub fn turn_on(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new("scene", "turn_on").target(target)
+}
+
+/// Activates the scene with a transition, for the lights that support it
+pub fn turn_on_with_transition(target: impl Into<Target>, transition: Duration) -> ServiceCall {
+    turn_on(target).data("transition", transition.as_secs_f64())
+}
@@ -0,0 +1,32 @@
+//! Services of the `script` domain
+use serde_json::Value;
+
+use super::{ServiceCall, Target};
+
+const DOMAIN: &str = "script";
+
+pub fn turn_on(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_on").target(target)
+}
+
+pub fn turn_off(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "turn_off").target(target)
+}
+
+pub fn toggle(target: impl Into<Target>) -> ServiceCall {
+    ServiceCall::new(DOMAIN, "toggle").target(target)
+}
+
+/// Runs the script by its object id, i.e. `wake_up` for `script.wake_up`, with
+/// the variables as service data. It waits for the script to finish.
+pub fn run(script: &str, variables: Option<Value>) -> ServiceCall {
+    let mut call = ServiceCall::new(DOMAIN, script);
+    if let Some(Value::Object(variables)) = variables {
+        call.data = variables;
+    }
+    call
+}
+
+pub fn reload() -> ServiceCall {
+    ServiceCall::new(DOMAIN, "reload")
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use super::{climate, cover, light, media_player, notify, switch, ServiceCall, Target};
+use crate::{CallService, HvacMode};
+
+fn to_json(service_call: impl Into<ServiceCall>) -> serde_json::Value {
+    serde_json::to_value(CallService::from(service_call.into())).unwrap()
+}
+
+#[test]
+fn light_turn_on_should_serialize() {
+    let cmd = to_json(
+        light::turn_on("light.kitchen")
+            .brightness_pct(40)
+            .transition(Duration::from_secs(2)),
+    );
+
+    assert_eq!(
+        cmd,
+        json!({
+            "id": null,
+            "type": "call_service",
+            "domain": "light",
+            "service": "turn_on",
+            "service_data": {"brightness_pct": 40, "transition": 2.0},
+            "target": {"entity_id": ["light.kitchen"]}
+        })
+    );
+}
+
+#[test]
+fn service_without_data_should_not_send_service_data() {
+    let cmd = to_json(switch::toggle(["switch.one", "switch.two"]));
+
+    assert_eq!(cmd["service_data"], json!(null));
+    assert_eq!(
+        cmd["target"],
+        json!({"entity_id": ["switch.one", "switch.two"]})
+    );
+}
+
+#[test]
+fn target_should_combine_areas_and_devices() {
+    let cmd = to_json(cover::set_position(
+        Target::area("living_room").and_device("abc123"),
+        150,
+    ));
+
+    assert_eq!(
+        cmd["target"],
+        json!({"device_id": ["abc123"], "area_id": ["living_room"]})
+    );
+    assert_eq!(cmd["service_data"], json!({"position": 100}));
+}
+
+#[test]
+fn climate_set_temperature_should_serialize() {
+    let cmd = to_json(
+        climate::set_temperature("climate.office")
+            .temperature(21.5)
+            .hvac_mode(HvacMode::Heat),
+    );
+
+    assert_eq!(cmd["service"], "set_temperature");
+    assert_eq!(
+        cmd["service_data"],
+        json!({"temperature": 21.5, "hvac_mode": "heat"})
+    );
+}
+
+#[test]
+fn media_player_play_media_should_serialize() {
+    let cmd = to_json(
+        media_player::play_media("media_player.kitchen", "http://radio/stream", "music")
+            .enqueue(media_player::Enqueue::Replace),
+    );
+
+    assert_eq!(
+        cmd["service_data"],
+        json!({
+            "media_content_id": "http://radio/stream",
+            "media_content_type": "music",
+            "enqueue": "replace"
+        })
+    );
+}
+
+#[test]
+fn notify_send_should_use_service_name() {
+    let cmd = to_json(notify::send("mobile_app_phone", "Door is open").title("Alarm"));
+
+    assert_eq!(cmd["domain"], "notify");
+    assert_eq!(cmd["service"], "mobile_app_phone");
+    assert_eq!(cmd["target"], json!(null));
+    assert_eq!(
+        cmd["service_data"],
+        json!({"message": "Door is open", "title": "Alarm"})
+    );
+}
+
+mod glob_import {
+    // the builders of a domain must not shadow the prelude when glob imported
+    use super::super::notify::*;
+
+    fn assert_send<T: Send>(_: T) {}
+
+    #[test]
+    fn notify_glob_import_should_keep_send_trait() {
+        assert_send(send("mobile_app_phone", "Door is open"));
+    }
+}
@@ -0,0 +1,302 @@
+use arc_swap::ArcSwap;
+use futures_util::{stream, Stream};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{
+    client::HaConnection, listener::Subscriptions, HaEventData, HaState, HassResult,
+    StateChangedEvent, WsEvent,
+};
+
+mod operators;
+mod query;
+pub use operators::StateStreamExt;
+pub(crate) use query::glob_match;
+pub use query::Query;
+
+#[cfg(test)]
+mod tests;
+
+/// The states of all entities by entity id
+///
+/// A persistent map, clones share all entries and an update only copies the
+/// path to the changed entry.
+pub type States = im::HashMap<Arc<str>, Arc<HaState>>;
+
+/// The number of changes buffered for each stream before the oldest are dropped
+const CHANGES_CAPACITY: usize = 256;
+
+/// The `state_changed` callback of the store on a connection
+struct Attachment {
+    connection: Weak<tokio::sync::Mutex<Subscriptions>>,
+    listener_id: u64,
+}
+
+impl Attachment {
+    fn is_on(&self, conn: &HaConnection) -> bool {
+        self.connection
+            .upgrade()
+            .is_some_and(|listeners| Arc::ptr_eq(&listeners, conn.listener_table()))
+    }
+}
+
+/// The registry information of an entity, with the area inherited from its device
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EntityInfo {
+    pub area_id: Option<String>,
+    pub device_id: Option<String>,
+    pub labels: Vec<String>,
+}
+
+/// A local mirror of the entity states in Home Assistant
+///
+/// The store is seeded from `get_states` and kept up to date with the
+/// `state_changed` events. Reads never wait for a lock, every update swaps in a
+/// new map so a snapshot is never changed after it is taken. The maps share
+/// their unchanged entries, so an update costs the same with thousands of entities.
+///
+/// The store is not bound to a connection. After a reconnect, call
+/// [`StateStore::attach`] with the new connection to re-sync all states, the
+/// store then only follows the new connection.
+///
+/// ```no_run
+/// # async fn example(conn: &mut r_hassclient::client::HaConnection) -> r_hassclient::HassResult<()> {
+/// use r_hassclient::StateStore;
+///
+/// let store = StateStore::new();
+/// store.attach(conn).await?;
+///
+/// if let Some(state) = store.get("light.kitchen") {
+///     println!("kitchen light is {}", state.state);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct StateStore {
+    states: Arc<ArcSwap<States>>,
+    registry: Arc<ArcSwap<HashMap<String, EntityInfo>>>,
+    changes: broadcast::Sender<Arc<StateChangedEvent>>,
+    attachment: Arc<Mutex<Option<Attachment>>>,
+    // Bumped on every attach and detach, callbacks of older attachments ignore events
+    generation: Arc<AtomicU64>,
+}
+
+impl Default for StateStore {
+    fn default() -> Self {
+        let (changes, _) = broadcast::channel(CHANGES_CAPACITY);
+        StateStore {
+            states: Default::default(),
+            registry: Default::default(),
+            changes,
+            attachment: Default::default(),
+            generation: Default::default(),
+        }
+    }
+}
+
+impl StateStore {
+    pub fn new() -> StateStore {
+        StateStore::default()
+    }
+
+    /// Subscribes to the state changes on the connection and syncs all states
+    ///
+    /// The subscription is made before the states are fetched so no change
+    /// is lost in between. Attaching again replaces the previous callback, so
+    /// every change is applied once. Returns the id of the callback.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the subscription or the sync fails.
+    pub async fn attach(&self, conn: &mut HaConnection) -> HassResult<u64> {
+        self.detach(conn).await?;
+        let generation = self.generation.load(Ordering::SeqCst);
+        let store = self.clone();
+        let listener_id = conn
+            .subscribe_event("state_changed", move |item: WsEvent| {
+                if store.generation.load(Ordering::SeqCst) != generation {
+                    return;
+                }
+                if let Ok(HaEventData::StateChangedEvent(event)) = item.event.event_data() {
+                    store.apply(event);
+                }
+            })
+            .await?;
+        *self.lock_attachment() = Some(Attachment {
+            connection: Arc::downgrade(conn.listener_table()),
+            listener_id,
+        });
+        self.sync(conn).await?;
+        Ok(listener_id)
+    }
+
+    /// Stops following the state changes, the states are kept
+    ///
+    /// The callback is removed from the connection when the store is attached
+    /// to it, the callback on another connection ignores the changes from now on.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if Home Assistant fails to cancel the subscription.
+    pub async fn detach(&self, conn: &mut HaConnection) -> HassResult<()> {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        let attachment = self.lock_attachment().take();
+        match attachment {
+            Some(attachment) if attachment.is_on(conn) => {
+                conn.unsubscribe(attachment.listener_id).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn lock_attachment(&self) -> std::sync::MutexGuard<'_, Option<Attachment>> {
+        self.attachment
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Replaces all states with the current states in Home Assistant
+    ///
+    /// Entities that were removed while the store was out of sync are removed.
+    pub async fn sync(&self, conn: &mut HaConnection) -> HassResult<()> {
+        let states = conn.get_states().await?;
+        self.replace(states);
+        Ok(())
+    }
+
+    /// Fetches the entity and device registries, used to query by area and label
+    ///
+    /// The registry is not kept up to date, call it again after changing areas
+    /// or labels in Home Assistant.
+    pub async fn sync_registry(&self, conn: &mut HaConnection) -> HassResult<()> {
+        let devices: HashMap<String, Option<String>> = conn
+            .get_device_registry()
+            .await?
+            .into_iter()
+            .map(|device| (device.id, device.area_id))
+            .collect();
+
+        let registry = conn
+            .get_entity_registry()
+            .await?
+            .into_iter()
+            .map(|entity| {
+                let area_id = entity.area_id.or_else(|| {
+                    entity
+                        .device_id
+                        .as_ref()
+                        .and_then(|device_id| devices.get(device_id).cloned().flatten())
+                });
+                let info = EntityInfo {
+                    area_id,
+                    device_id: entity.device_id,
+                    labels: entity.labels,
+                };
+                (entity.entity_id, info)
+            })
+            .collect();
+        self.registry.store(Arc::new(registry));
+        Ok(())
+    }
+
+    /// Replaces all states in the store
+    pub fn replace(&self, states: Vec<HaState>) {
+        let states = states
+            .into_iter()
+            .map(|state| (Arc::from(state.entity_id.as_str()), Arc::new(state)))
+            .collect();
+        self.states.store(Arc::new(states));
+    }
+
+    /// Replaces the registry information of the entities
+    pub fn replace_registry(&self, registry: HashMap<String, EntityInfo>) {
+        self.registry.store(Arc::new(registry));
+    }
+
+    /// Applies a state change, a change without new state removes the entity
+    pub fn apply(&self, event: &StateChangedEvent) {
+        let new_state = event.new_state.clone().map(Arc::new);
+        let entity_id: Arc<str> = Arc::from(event.entity_id.as_str());
+        // A retry only repeats the update of one entry
+        self.states.rcu(|states| match &new_state {
+            Some(new_state) => states.update(Arc::clone(&entity_id), Arc::clone(new_state)),
+            None => states.without(&*entity_id),
+        });
+        // No one might be listening, that is fine
+        _ = self.changes.send(Arc::new(event.clone()));
+    }
+
+    /// The current state of the entity
+    pub fn get(&self, entity_id: &str) -> Option<Arc<HaState>> {
+        self.states.load().get(entity_id).cloned()
+    }
+
+    /// The current states of all entities, the snapshot is not changed by later updates
+    pub fn snapshot(&self) -> Arc<States> {
+        self.states.load_full()
+    }
+
+    /// The registry information of the entity, if the registry is synced
+    pub fn info(&self, entity_id: &str) -> Option<EntityInfo> {
+        self.registry.load().get(entity_id).cloned()
+    }
+
+    /// The current states of the entities matching the query, sorted by entity id
+    pub fn query(&self, query: &Query) -> Vec<HaState> {
+        let registry = self.registry.load();
+        let mut states: Vec<HaState> = self
+            .states
+            .load()
+            .values()
+            .filter(|state| query.matches(state, registry.get(&state.entity_id)))
+            .map(|state| HaState::clone(state))
+            .collect();
+        states.sort_by(|a, b| a.entity_id.cmp(&b.entity_id));
+        states
+    }
+
+    /// A stream of the changes to entities matching the query
+    ///
+    /// A change matches if its new state matches, or for removed entities, if
+    /// the old state matched. Changes are dropped for streams that fall more
+    /// than 256 changes behind.
+    pub fn watch(&self, query: Query) -> impl Stream<Item = Arc<StateChangedEvent>> {
+        let rx = self.changes.subscribe();
+        let registry = Arc::clone(&self.registry);
+        stream::unfold(
+            (rx, query, registry),
+            |(mut rx, query, registry)| async move {
+                loop {
+                    match rx.recv().await {
+                        Ok(event) => {
+                            let state = event.new_state.as_ref().or(event.old_state.as_ref());
+                            let info = registry.load();
+                            if let Some(state) = state {
+                                if query.matches(state, info.get(&event.entity_id)) {
+                                    return Some((event, (rx, query, registry)));
+                                }
+                            }
+                        }
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            },
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.load().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.load().is_empty()
+    }
+}
@@ -0,0 +1,180 @@
+use futures_util::{
+    stream::{self, BoxStream},
+    Stream, StreamExt,
+};
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    future,
+};
+use tokio::time::{sleep_until, Duration, Instant};
+
+use crate::{HaState, StateChangedEvent};
+
+/// Operators for streams of state changes, like the stream from [`crate::StateStore::watch`]
+///
+/// The operators work on any stream of `StateChangedEvent` or `Arc<StateChangedEvent>`
+/// and use tokio time, so they can be tested with paused time.
+///
+/// ```no_run
+/// # async fn example(store: r_hassclient::StateStore) {
+/// use futures_util::StreamExt;
+/// use r_hassclient::{Query, StateStreamExt};
+/// use std::time::Duration;
+///
+/// let mut no_motion = store
+///     .watch(Query::new().entity_id("binary_sensor.hallway_motion"))
+///     .held_for(Duration::from_secs(5 * 60), |state| state.state == "off");
+///
+/// while let Some(event) = no_motion.next().await {
+///     println!("no motion in the hallway since {:?}", event.new_state);
+/// }
+/// # }
+/// ```
+pub trait StateStreamExt: Stream + Sized + Send + 'static
+where
+    Self::Item: Borrow<StateChangedEvent> + Send + 'static,
+{
+    /// Emits the latest change once no new change arrived for the period
+    fn debounce(self, period: Duration) -> BoxStream<'static, Self::Item> {
+        stream::unfold(
+            (self.boxed(), None::<Self::Item>, false),
+            move |(mut inner, mut latest, mut ended)| async move {
+                loop {
+                    if ended {
+                        return latest.map(|item| (item, (inner, None, true)));
+                    }
+                    match latest.take() {
+                        None => match inner.next().await {
+                            Some(item) => latest = Some(item),
+                            None => return None,
+                        },
+                        Some(item) => {
+                            let deadline = Instant::now() + period;
+                            tokio::select! {
+                                next = inner.next() => match next {
+                                    Some(next) => latest = Some(next),
+                                    None => {
+                                        latest = Some(item);
+                                        ended = true;
+                                    }
+                                },
+                                _ = sleep_until(deadline) => {
+                                    return Some((item, (inner, None, false)));
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+        )
+        .boxed()
+    }
+
+    /// Emits a change and then drops all changes for the period
+    fn throttle(self, period: Duration) -> BoxStream<'static, Self::Item> {
+        let mut next_allowed: Option<Instant> = None;
+        self.filter(move |_| {
+            let now = Instant::now();
+            let allowed = next_allowed.is_none_or(|next_allowed| now >= next_allowed);
+            if allowed {
+                next_allowed = Some(now + period);
+            }
+            future::ready(allowed)
+        })
+        .boxed()
+    }
+
+    /// Emits a change when the new state has matched the predicate for the duration
+    ///
+    /// The emitted change is the one that made the state match. Each entity is
+    /// tracked on its own and fires once, until its state stops matching and
+    /// matches again.
+    fn held_for<F>(self, duration: Duration, predicate: F) -> BoxStream<'static, Self::Item>
+    where
+        F: Fn(&HaState) -> bool + Send + 'static,
+    {
+        let held = HeldFor {
+            pending: HashMap::new(),
+            fired: HashSet::new(),
+        };
+        stream::unfold(
+            (self.boxed(), held, predicate),
+            move |(mut inner, mut held, predicate)| async move {
+                loop {
+                    let first_deadline = held.pending.values().map(|(_, at)| *at).min();
+                    tokio::select! {
+                        next = inner.next() => {
+                            let item = next?;
+                            let event: &StateChangedEvent = item.borrow();
+                            let entity_id = event.entity_id.clone();
+                            if event.new_state.as_ref().is_some_and(&predicate) {
+                                if !held.fired.contains(&entity_id) {
+                                    // Later matching changes do not restart the timer
+                                    held.pending
+                                        .entry(entity_id)
+                                        .or_insert_with(|| (item, Instant::now() + duration));
+                                }
+                            } else {
+                                held.pending.remove(&entity_id);
+                                held.fired.remove(&entity_id);
+                            }
+                        }
+                        _ = sleep_until(first_deadline.unwrap_or_else(Instant::now)),
+                            if first_deadline.is_some() => {
+                            let now = Instant::now();
+                            let due = held
+                                .pending
+                                .iter()
+                                .find(|(_, (_, at))| *at <= now)
+                                .map(|(entity_id, _)| entity_id.clone());
+                            if let Some(entity_id) = due {
+                                let (item, _) = held.pending.remove(&entity_id)?;
+                                held.fired.insert(entity_id);
+                                return Some((item, (inner, held, predicate)));
+                            }
+                        }
+                    }
+                }
+            },
+        )
+        .boxed()
+    }
+
+    /// Drops the changes that only changed the attributes
+    fn distinct_state(self) -> BoxStream<'static, Self::Item> {
+        self.filter(|item| {
+            let event: &StateChangedEvent = item.borrow();
+            let old = event.old_state.as_ref().map(|state| &state.state);
+            let new = event.new_state.as_ref().map(|state| &state.state);
+            future::ready(old != new)
+        })
+        .boxed()
+    }
+
+    /// Keeps the changes from the `from` state to the `to` state
+    fn changed_from_to(self, from: &str, to: &str) -> BoxStream<'static, Self::Item> {
+        let from = from.to_owned();
+        let to = to.to_owned();
+        self.filter(move |item| {
+            let event: &StateChangedEvent = item.borrow();
+            let old = event.old_state.as_ref().map(|state| state.state.as_str());
+            let new = event.new_state.as_ref().map(|state| state.state.as_str());
+            future::ready(old == Some(from.as_str()) && new == Some(to.as_str()))
+        })
+        .boxed()
+    }
+}
+
+impl<S> StateStreamExt for S
+where
+    S: Stream + Sized + Send + 'static,
+    S::Item: Borrow<StateChangedEvent> + Send + 'static,
+{
+}
+
+/// The entities waiting to have matched long enough, and the ones that already fired
+struct HeldFor<T> {
+    pending: HashMap<String, (T, Instant)>,
+    fired: HashSet<String>,
+}
@@ -0,0 +1,166 @@
+use serde_json::Value;
+use std::{fmt, sync::Arc};
+
+use crate::HaState;
+
+use super::EntityInfo;
+
+/// A filter over the entities in a [`StateStore`](super::StateStore)
+///
+/// All filters must match for an entity to match the query.
+///
+/// ```
+/// use r_hassclient::Query;
+///
+/// let query = Query::new()
+///     .domain("binary_sensor")
+///     .device_class("motion")
+///     .area("living_room")
+///     .state_is("on");
+/// ```
+#[derive(Clone, Default)]
+pub struct Query {
+    filters: Vec<Filter>,
+}
+
+#[derive(Clone)]
+enum Filter {
+    Domain(String),
+    EntityId(String),
+    Attribute(String, Value),
+    DeviceClass(String),
+    Area(String),
+    Label(String),
+    State(Arc<dyn Fn(&HaState) -> bool + Send + Sync>),
+}
+
+impl Query {
+    pub fn new() -> Query {
+        Query::default()
+    }
+
+    pub fn domain(self, domain: &str) -> Query {
+        self.filter(Filter::Domain(domain.to_owned()))
+    }
+
+    /// Matches the entity id with a glob pattern, `*` matches any number of
+    /// characters and `?` matches a single character, i.e. `light.*_ceiling`
+    pub fn entity_id(self, pattern: &str) -> Query {
+        self.filter(Filter::EntityId(pattern.to_owned()))
+    }
+
+    pub fn attribute(self, key: &str, value: impl Into<Value>) -> Query {
+        self.filter(Filter::Attribute(key.to_owned(), value.into()))
+    }
+
+    pub fn device_class(self, device_class: &str) -> Query {
+        self.filter(Filter::DeviceClass(device_class.to_owned()))
+    }
+
+    /// Matches entities in the area, directly or through their device
+    ///
+    /// Needs the registry in the store, see [`StateStore::sync_registry`](super::StateStore::sync_registry).
+    pub fn area(self, area_id: &str) -> Query {
+        self.filter(Filter::Area(area_id.to_owned()))
+    }
+
+    /// Matches entities with the label
+    ///
+    /// Needs the registry in the store, see [`StateStore::sync_registry`](super::StateStore::sync_registry).
+    pub fn label(self, label_id: &str) -> Query {
+        self.filter(Filter::Label(label_id.to_owned()))
+    }
+
+    /// Matches entities where the predicate returns true
+    pub fn state(self, predicate: impl Fn(&HaState) -> bool + Send + Sync + 'static) -> Query {
+        self.filter(Filter::State(Arc::new(predicate)))
+    }
+
+    /// Matches entities with the state
+    pub fn state_is(self, state: &str) -> Query {
+        let state = state.to_owned();
+        self.state(move |s| s.state == state)
+    }
+
+    /// Checks if the state, with the registry info of the entity, matches the query
+    pub fn matches(&self, state: &HaState, info: Option<&EntityInfo>) -> bool {
+        self.filters.iter().all(|filter| match filter {
+            Filter::Domain(domain) => state.domain() == domain,
+            Filter::EntityId(pattern) => glob_match(pattern, &state.entity_id),
+            Filter::Attribute(key, value) => attribute(state, key) == Some(value),
+            Filter::DeviceClass(device_class) => {
+                attribute(state, "device_class").and_then(Value::as_str)
+                    == Some(device_class.as_str())
+            }
+            Filter::Area(area_id) => {
+                info.and_then(|info| info.area_id.as_deref()) == Some(area_id.as_str())
+            }
+            Filter::Label(label_id) => info.is_some_and(|info| info.labels.contains(label_id)),
+            Filter::State(predicate) => predicate(state),
+        })
+    }
+
+    fn filter(mut self, filter: Filter) -> Query {
+        self.filters.push(filter);
+        self
+    }
+}
+
+impl fmt::Debug for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.filters).finish()
+    }
+}
+
+impl fmt::Debug for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Domain(domain) => write!(f, "domain == {}", domain),
+            Filter::EntityId(pattern) => write!(f, "entity_id like {}", pattern),
+            Filter::Attribute(key, value) => write!(f, "{} == {}", key, value),
+            Filter::DeviceClass(device_class) => write!(f, "device_class == {}", device_class),
+            Filter::Area(area_id) => write!(f, "area == {}", area_id),
+            Filter::Label(label_id) => write!(f, "label == {}", label_id),
+            Filter::State(_) => write!(f, "state predicate"),
+        }
+    }
+}
+
+fn attribute<'a>(state: &'a HaState, key: &str) -> Option<&'a Value> {
+    state
+        .attributes
+        .as_ref()
+        .and_then(|attributes| attributes.get(key))
+}
+
+/// Matches text against a pattern where `*` is any number of characters and `?` one character
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text position it matched from
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` match one more character and try again
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,313 @@
+use futures_util::{stream, Stream, StreamExt};
+use serde_json::json;
+use std::collections::HashMap;
+use tokio::{
+    sync::mpsc,
+    time::{sleep, timeout, Duration, Instant},
+};
+
+use super::{query::glob_match, EntityInfo, Query, StateStore, StateStreamExt};
+use crate::{HaState, StateChangedEvent};
+
+fn state(entity_id: &str, state: &str) -> HaState {
+    HaState {
+        entity_id: entity_id.to_owned(),
+        attributes: None,
+        state: state.to_owned(),
+        ..Default::default()
+    }
+}
+
+fn change(entity_id: &str, new_state: Option<&str>) -> StateChangedEvent {
+    StateChangedEvent {
+        entity_id: entity_id.to_owned(),
+        new_state: new_state.map(|new_state| state(entity_id, new_state)),
+        old_state: None,
+    }
+}
+
+#[test]
+fn changes_should_update_the_store() {
+    let store = StateStore::new();
+    store.replace(vec![
+        state("light.kitchen", "off"),
+        state("switch.fan", "on"),
+    ]);
+
+    store.apply(&change("light.kitchen", Some("on")));
+    store.apply(&change("sensor.new", Some("12")));
+
+    assert_eq!(store.get("light.kitchen").unwrap().state, "on");
+    assert_eq!(store.get("sensor.new").unwrap().state, "12");
+    assert_eq!(store.len(), 3);
+}
+
+#[test]
+fn removed_entity_should_be_removed_from_store() {
+    let store = StateStore::new();
+    store.replace(vec![state("light.kitchen", "off")]);
+
+    store.apply(&change("light.kitchen", None));
+
+    assert!(store.get("light.kitchen").is_none());
+    assert!(store.is_empty());
+}
+
+#[test]
+fn snapshot_should_not_change_after_update() {
+    let store = StateStore::new();
+    store.replace(vec![state("light.kitchen", "off")]);
+
+    let snapshot = store.snapshot();
+    store.apply(&change("light.kitchen", Some("on")));
+
+    assert_eq!(snapshot["light.kitchen"].state, "off");
+    assert_eq!(store.get("light.kitchen").unwrap().state, "on");
+}
+
+#[test]
+fn resync_should_drop_stale_entities() {
+    let store = StateStore::new();
+    store.replace(vec![
+        state("light.kitchen", "off"),
+        state("light.gone", "on"),
+    ]);
+
+    store.replace(vec![state("light.kitchen", "on")]);
+
+    assert!(store.get("light.gone").is_none());
+    assert_eq!(store.get("light.kitchen").unwrap().state, "on");
+}
+
+fn sensor(entity_id: &str, value: &str, device_class: &str) -> HaState {
+    HaState {
+        entity_id: entity_id.to_owned(),
+        attributes: Some(HashMap::from([(
+            "device_class".to_owned(),
+            json!(device_class),
+        )])),
+        state: value.to_owned(),
+        ..Default::default()
+    }
+}
+
+fn populated_store() -> StateStore {
+    let store = StateStore::new();
+    store.replace(vec![
+        state("light.kitchen_ceiling", "on"),
+        state("light.bedroom_ceiling", "off"),
+        state("light.kitchen_table", "on"),
+        sensor("binary_sensor.hall_motion", "on", "motion"),
+        sensor("binary_sensor.front_door", "off", "door"),
+        sensor("sensor.outside", "12.5", "temperature"),
+    ]);
+    store.replace_registry(HashMap::from([
+        (
+            "light.kitchen_table".to_owned(),
+            EntityInfo {
+                area_id: Some("kitchen".to_owned()),
+                labels: vec!["dining".to_owned()],
+                ..Default::default()
+            },
+        ),
+        (
+            "light.kitchen_ceiling".to_owned(),
+            EntityInfo {
+                area_id: Some("kitchen".to_owned()),
+                ..Default::default()
+            },
+        ),
+    ]));
+    store
+}
+
+fn ids(states: Vec<HaState>) -> Vec<String> {
+    states.into_iter().map(|state| state.entity_id).collect()
+}
+
+#[test]
+fn query_should_filter_by_domain_and_state() {
+    let store = populated_store();
+
+    let lights_on = store.query(&Query::new().domain("light").state_is("on"));
+
+    assert_eq!(
+        ids(lights_on),
+        vec!["light.kitchen_ceiling", "light.kitchen_table"]
+    );
+}
+
+#[test]
+fn query_should_filter_by_glob_pattern() {
+    let store = populated_store();
+
+    let ceilings = store.query(&Query::new().entity_id("light.*_ceiling"));
+
+    assert_eq!(
+        ids(ceilings),
+        vec!["light.bedroom_ceiling", "light.kitchen_ceiling"]
+    );
+}
+
+#[test]
+fn query_should_filter_by_device_class_and_attribute() {
+    let store = populated_store();
+
+    let motion = store.query(&Query::new().device_class("motion"));
+    let doors = store.query(&Query::new().attribute("device_class", "door"));
+    let warm = store
+        .query(&Query::new().state(|s| s.state.parse::<f64>().is_ok_and(|value| value > 10.0)));
+
+    assert_eq!(ids(motion), vec!["binary_sensor.hall_motion"]);
+    assert_eq!(ids(doors), vec!["binary_sensor.front_door"]);
+    assert_eq!(ids(warm), vec!["sensor.outside"]);
+}
+
+#[test]
+fn query_should_filter_by_area_and_label() {
+    let store = populated_store();
+
+    let kitchen = store.query(&Query::new().area("kitchen"));
+    let dining = store.query(&Query::new().label("dining"));
+
+    assert_eq!(
+        ids(kitchen),
+        vec!["light.kitchen_ceiling", "light.kitchen_table"]
+    );
+    assert_eq!(ids(dining), vec!["light.kitchen_table"]);
+}
+
+#[test]
+fn glob_should_match_wildcards() {
+    assert!(glob_match("light.*", "light.kitchen"));
+    assert!(glob_match("*.kitchen_*", "light.kitchen_table"));
+    assert!(glob_match("sensor.temp_?", "sensor.temp_1"));
+    assert!(!glob_match("sensor.temp_?", "sensor.temp_12"));
+    assert!(!glob_match("light.*", "switch.light"));
+}
+
+#[tokio::test]
+async fn watch_should_stream_matching_changes() {
+    let store = populated_store();
+    let mut changes = Box::pin(store.watch(Query::new().domain("light").state_is("off")));
+
+    store.apply(&change("switch.fan", Some("off")));
+    store.apply(&change("light.kitchen_table", Some("on")));
+    store.apply(&change("light.kitchen_ceiling", Some("off")));
+
+    let event = changes.next().await.unwrap();
+    assert_eq!(event.entity_id, "light.kitchen_ceiling");
+}
+
+fn transition(entity_id: &str, from: &str, to: &str) -> StateChangedEvent {
+    StateChangedEvent {
+        entity_id: entity_id.to_owned(),
+        new_state: Some(state(entity_id, to)),
+        old_state: Some(state(entity_id, from)),
+    }
+}
+
+fn channel_stream() -> (
+    mpsc::UnboundedSender<StateChangedEvent>,
+    impl Stream<Item = StateChangedEvent>,
+) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let changes = stream::unfold(rx, |mut rx| async move {
+        let event = rx.recv().await?;
+        Some((event, rx))
+    });
+    (tx, changes)
+}
+
+#[tokio::test(start_paused = true)]
+async fn debounce_should_emit_the_last_change_after_quiet_period() {
+    let (tx, changes) = channel_stream();
+    let mut changes = changes.debounce(Duration::from_secs(1));
+    let start = Instant::now();
+
+    tx.send(transition("sensor.power", "1", "2")).unwrap();
+    sleep(Duration::from_millis(300)).await;
+    tx.send(transition("sensor.power", "2", "3")).unwrap();
+
+    let event = changes.next().await.unwrap();
+    assert_eq!(event.new_state.unwrap().state, "3");
+    assert_eq!(start.elapsed(), Duration::from_millis(1300));
+}
+
+#[tokio::test(start_paused = true)]
+async fn throttle_should_drop_changes_within_period() {
+    let (tx, changes) = channel_stream();
+    let mut changes = changes.throttle(Duration::from_secs(1));
+
+    tx.send(transition("sensor.power", "1", "2")).unwrap();
+    let first = changes.next().await.unwrap();
+    assert_eq!(first.new_state.unwrap().state, "2");
+
+    tx.send(transition("sensor.power", "2", "3")).unwrap();
+    let dropped = timeout(Duration::from_millis(500), changes.next()).await;
+    assert!(dropped.is_err());
+
+    sleep(Duration::from_millis(500)).await;
+    tx.send(transition("sensor.power", "3", "4")).unwrap();
+    let second = changes.next().await.unwrap();
+    assert_eq!(second.new_state.unwrap().state, "4");
+}
+
+#[tokio::test(start_paused = true)]
+async fn held_for_should_fire_when_state_holds() {
+    let (tx, changes) = channel_stream();
+    let mut changes = changes.held_for(Duration::from_secs(300), |state| state.state == "off");
+    let start = Instant::now();
+
+    tx.send(transition("binary_sensor.motion", "on", "off"))
+        .unwrap();
+
+    let event = changes.next().await.unwrap();
+    assert_eq!(event.entity_id, "binary_sensor.motion");
+    assert_eq!(start.elapsed(), Duration::from_secs(300));
+}
+
+#[tokio::test(start_paused = true)]
+async fn held_for_should_not_fire_when_state_changes_back() {
+    let (tx, changes) = channel_stream();
+    let mut changes = changes.held_for(Duration::from_secs(300), |state| state.state == "off");
+
+    tx.send(transition("binary_sensor.motion", "on", "off"))
+        .unwrap();
+    sleep(Duration::from_secs(200)).await;
+    tx.send(transition("binary_sensor.motion", "off", "on"))
+        .unwrap();
+
+    let next = timeout(Duration::from_secs(600), changes.next()).await;
+    assert!(next.is_err());
+}
+
+#[tokio::test]
+async fn distinct_state_should_drop_attribute_changes() {
+    let mut attribute_change = transition("light.kitchen", "on", "on");
+    attribute_change.new_state.as_mut().unwrap().attributes =
+        Some(HashMap::from([("brightness".to_owned(), json!(100))]));
+    let changes = stream::iter(vec![
+        attribute_change,
+        transition("light.kitchen", "on", "off"),
+    ]);
+
+    let states: Vec<String> = changes
+        .distinct_state()
+        .map(|event| event.new_state.unwrap().state)
+        .collect()
+        .await;
+    assert_eq!(states, vec!["off"]);
+}
+
+#[tokio::test]
+async fn changed_from_to_should_keep_matching_transitions() {
+    let changes = stream::iter(vec![
+        transition("lock.door", "locked", "unlocked"),
+        transition("lock.door", "unlocked", "locked"),
+        transition("lock.door", "jammed", "locked"),
+    ]);
+
+    let count = changes.changed_from_to("unlocked", "locked").count().await;
+    assert_eq!(count, 1);
+}
@@ -0,0 +1,196 @@
+//! TLS for `wss` connections, with the `rustls` or `native-tls` feature
+//!
+//! [`TlsConfig`] is set with [`crate::client::HaClientBuilder::tls`] for
+//! installations behind HTTPS with a private certificate authority, a
+//! self-signed certificate or a reverse proxy asking for client certificates.
+//!
+//! ```no_run
+//! # async fn example() -> r_hassclient::HassResult<()> {
+//! use r_hassclient::{tls::TlsConfig, HaClient};
+//!
+//! let tls = TlsConfig::new().root_certificate_file("/etc/ssl/private-ca.pem")?;
+//! let url = url::Url::parse("wss://ha.example.com/api/websocket")?;
+//! let mut conn = HaClient::builder().tls(tls).build().connect_async(url).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use url::Host;
+
+use crate::{auth::Secret, transport::ByteStream, HassError, HassResult};
+
+#[cfg(feature = "native-tls")]
+mod native_backend;
+#[cfg(feature = "rustls")]
+mod rustls_backend;
+
+/// The TLS implementation, rustls is used when both features are enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsBackend {
+    #[cfg(feature = "rustls")]
+    Rustls,
+    #[cfg(feature = "native-tls")]
+    NativeTls,
+}
+
+#[cfg(feature = "rustls")]
+const DEFAULT_BACKEND: TlsBackend = TlsBackend::Rustls;
+#[cfg(not(feature = "rustls"))]
+const DEFAULT_BACKEND: TlsBackend = TlsBackend::NativeTls;
+
+impl Default for TlsBackend {
+    fn default() -> Self {
+        DEFAULT_BACKEND
+    }
+}
+
+/// How the certificate of Home Assistant is verified, and the client certificate
+///
+/// By default the certificate must be signed by one of the public
+/// certificate authorities trusted by the backend.
+#[derive(Clone, Default)]
+pub struct TlsConfig {
+    backend: TlsBackend,
+    root_certificates: Vec<String>,
+    client_identity: Option<ClientIdentity>,
+    pinned_sha256: Vec<[u8; 32]>,
+    accept_invalid_certs: bool,
+}
+
+#[derive(Clone)]
+struct ClientIdentity {
+    certificate_chain: String,
+    private_key: Secret,
+}
+
+impl TlsConfig {
+    pub fn new() -> TlsConfig {
+        TlsConfig::default()
+    }
+
+    pub fn backend(mut self, backend: TlsBackend) -> TlsConfig {
+        self.backend = backend;
+        self
+    }
+
+    /// Also trusts the PEM encoded certificate, like the one of a private certificate authority
+    pub fn root_certificate(mut self, pem: &str) -> TlsConfig {
+        self.root_certificates.push(pem.to_owned());
+        self
+    }
+
+    /// Also trusts the PEM encoded certificate in the file
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can not be read.
+    pub fn root_certificate_file(self, path: impl AsRef<Path>) -> HassResult<TlsConfig> {
+        Ok(self.root_certificate(&read_file(path.as_ref())?))
+    }
+
+    /// The PEM encoded certificate chain and PKCS#8 private key sent to the server
+    ///
+    /// Used for mutual TLS, like with a reverse proxy in front of Home Assistant.
+    pub fn client_identity(
+        mut self,
+        certificate_chain_pem: &str,
+        private_key_pem: &str,
+    ) -> TlsConfig {
+        self.client_identity = Some(ClientIdentity {
+            certificate_chain: certificate_chain_pem.to_owned(),
+            private_key: Secret::from(private_key_pem),
+        });
+        self
+    }
+
+    /// The client certificate chain and private key from PEM files
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a file can not be read.
+    pub fn client_identity_files(
+        self,
+        certificate_chain: impl AsRef<Path>,
+        private_key: impl AsRef<Path>,
+    ) -> HassResult<TlsConfig> {
+        let certificate_chain = read_file(certificate_chain.as_ref())?;
+        let private_key = Secret::new(read_file(private_key.as_ref())?);
+        Ok(self.client_identity(&certificate_chain, private_key.expose()))
+    }
+
+    /// Only accepts a server certificate with this SHA-256 fingerprint
+    ///
+    /// The fingerprint is hex, with or without colons, like the output of
+    /// `openssl x509 -noout -fingerprint -sha256`. A pinned certificate is
+    /// accepted without checking its certificate authority, so a self-signed
+    /// certificate can be pinned.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the fingerprint is not 32 hex encoded bytes.
+    pub fn pin_sha256(mut self, fingerprint: &str) -> HassResult<TlsConfig> {
+        let invalid = || HassError::Tls(format!("invalid SHA-256 fingerprint {}", fingerprint));
+        let hex: Vec<u8> = fingerprint.bytes().filter(|byte| *byte != b':').collect();
+        if hex.len() != 64 || !hex.iter().all(u8::is_ascii_hexdigit) {
+            return Err(invalid());
+        }
+        let mut pin = [0; 32];
+        for (byte, pair) in pin.iter_mut().zip(hex.chunks(2)) {
+            *byte = hex_value(pair[0]) << 4 | hex_value(pair[1]);
+        }
+        self.pinned_sha256.push(pin);
+        Ok(self)
+    }
+
+    /// Accepts any server certificate, only for labs and tests
+    ///
+    /// The connection is then open to man-in-the-middle attacks.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> TlsConfig {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    fn is_pinned(&self, certificate_der: &[u8]) -> bool {
+        let fingerprint: [u8; 32] = Sha256::digest(certificate_der).into();
+        self.pinned_sha256.contains(&fingerprint)
+    }
+}
+
+/// Runs the TLS handshake for the host of the url over the stream
+pub(crate) async fn wrap<S>(
+    stream: S,
+    host: &Host<&str>,
+    config: &TlsConfig,
+) -> HassResult<Box<dyn ByteStream>>
+where
+    S: ByteStream + 'static,
+{
+    // The name checked against the certificate, IPv6 addresses without the brackets of urls
+    let host = match host {
+        Host::Domain(domain) => domain.to_string(),
+        Host::Ipv4(addr) => addr.to_string(),
+        Host::Ipv6(addr) => addr.to_string(),
+    };
+    match config.backend {
+        #[cfg(feature = "rustls")]
+        TlsBackend::Rustls => rustls_backend::connect(stream, &host, config).await,
+        #[cfg(feature = "native-tls")]
+        TlsBackend::NativeTls => native_backend::connect(stream, &host, config).await,
+    }
+}
+
+// the value of a checked hex digit
+fn hex_value(digit: u8) -> u8 {
+    (digit as char).to_digit(16).unwrap_or_default() as u8
+}
+
+fn read_file(path: &Path) -> HassResult<String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| HassError::Tls(format!("can not read {}: {}", path.display(), err)))
+}
+
+fn tls_error(error: impl std::fmt::Display) -> HassError {
+    HassError::Tls(error.to_string())
+}
@@ -0,0 +1,48 @@
+use native_tls::{Certificate, Identity};
+
+use super::{tls_error, TlsConfig};
+use crate::{transport::ByteStream, HassError, HassResult};
+
+pub(super) async fn connect<S>(
+    stream: S,
+    host: &str,
+    config: &TlsConfig,
+) -> HassResult<Box<dyn ByteStream>>
+where
+    S: ByteStream + 'static,
+{
+    let mut builder = native_tls::TlsConnector::builder();
+    for pem in &config.root_certificates {
+        builder.add_root_certificate(Certificate::from_pem(pem.as_bytes()).map_err(tls_error)?);
+    }
+    if let Some(identity) = &config.client_identity {
+        builder.identity(
+            Identity::from_pkcs8(
+                identity.certificate_chain.as_bytes(),
+                identity.private_key.expose().as_bytes(),
+            )
+            .map_err(tls_error)?,
+        );
+    }
+    // Native backends can not verify pins during the handshake, they are checked after it
+    let pinned = !config.pinned_sha256.is_empty();
+    builder.danger_accept_invalid_certs(config.accept_invalid_certs || pinned);
+    let connector = tokio_native_tls::TlsConnector::from(builder.build().map_err(tls_error)?);
+    let stream = connector.connect(host, stream).await.map_err(tls_error)?;
+
+    if pinned && !config.accept_invalid_certs {
+        let certificate = stream
+            .get_ref()
+            .peer_certificate()
+            .map_err(tls_error)?
+            .map(|certificate| certificate.to_der())
+            .transpose()
+            .map_err(tls_error)?;
+        if !certificate.is_some_and(|der| config.is_pinned(&der)) {
+            return Err(HassError::Tls(
+                "the certificate does not match a pinned fingerprint".to_owned(),
+            ));
+        }
+    }
+    Ok(Box::new(stream))
+}
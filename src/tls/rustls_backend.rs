@@ -0,0 +1,157 @@
+use std::sync::Arc;
+use tokio_rustls::{
+    rustls::{
+        client::{
+            danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+            WebPkiServerVerifier,
+        },
+        crypto::{self, CryptoProvider},
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime},
+        ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    },
+    TlsConnector,
+};
+
+use super::{tls_error, TlsConfig};
+use crate::{transport::ByteStream, HassError, HassResult};
+
+pub(super) async fn connect<S>(
+    stream: S,
+    host: &str,
+    config: &TlsConfig,
+) -> HassResult<Box<dyn ByteStream>>
+where
+    S: ByteStream + 'static,
+{
+    let connector = TlsConnector::from(Arc::new(client_config(config)?));
+    let server_name = ServerName::try_from(host.to_owned()).map_err(tls_error)?;
+    let stream = connector
+        .connect(server_name, stream)
+        .await
+        .map_err(tls_error)?;
+    Ok(Box::new(stream))
+}
+
+fn client_config(config: &TlsConfig) -> HassResult<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    for pem in &config.root_certificates {
+        for certificate in certificates(pem)? {
+            roots.add(certificate).map_err(tls_error)?;
+        }
+    }
+
+    let provider = Arc::new(crypto::ring::default_provider());
+    let webpki =
+        WebPkiServerVerifier::builder_with_provider(Arc::new(roots), Arc::clone(&provider))
+            .build()
+            .map_err(tls_error)?;
+    let verifier = Verifier {
+        webpki,
+        provider: Arc::clone(&provider),
+        config: config.clone(),
+    };
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(tls_error)?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier));
+
+    match &config.client_identity {
+        Some(identity) => builder
+            .with_client_auth_cert(
+                certificates(&identity.certificate_chain)?,
+                private_key(identity.private_key.expose())?,
+            )
+            .map_err(tls_error),
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+fn certificates(pem: &str) -> HassResult<Vec<CertificateDer<'static>>> {
+    let certificates = rustls_pemfile::certs(&mut pem.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(tls_error)?;
+    if certificates.is_empty() {
+        return Err(HassError::Tls("no PEM certificate found".to_owned()));
+    }
+    Ok(certificates)
+}
+
+fn private_key(pem: &str) -> HassResult<PrivateKeyDer<'static>> {
+    rustls_pemfile::private_key(&mut pem.as_bytes())
+        .map_err(tls_error)?
+        .ok_or_else(|| HassError::Tls("no PEM private key found".to_owned()))
+}
+
+/// Verifies with the certificate authorities, unless the certificate is pinned or any is accepted
+struct Verifier {
+    webpki: Arc<WebPkiServerVerifier>,
+    provider: Arc<CryptoProvider>,
+    config: TlsConfig,
+}
+
+impl std::fmt::Debug for Verifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Verifier").finish_non_exhaustive()
+    }
+}
+
+impl ServerCertVerifier for Verifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        if self.config.accept_invalid_certs {
+            return Ok(ServerCertVerified::assertion());
+        }
+        if !self.config.pinned_sha256.is_empty() {
+            return match self.config.is_pinned(end_entity) {
+                true => Ok(ServerCertVerified::assertion()),
+                false => Err(tokio_rustls::rustls::Error::General(
+                    "the certificate does not match a pinned fingerprint".to_owned(),
+                )),
+            };
+        }
+        self.webpki
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
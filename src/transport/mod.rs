@@ -0,0 +1,133 @@
+use futures_util::{sink, stream, Sink, SinkExt, Stream, StreamExt};
+use std::pin::Pin;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+};
+use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+use url::Host;
+
+use crate::{HassError, HassResult};
+
+mod proxy;
+pub(crate) use proxy::connect_through;
+
+mod replay;
+pub use replay::{Direction, RecordedFrame, RecordingTransport, Replay, ReplayHandle, ReplaySpeed};
+
+#[cfg(test)]
+mod tests;
+
+/// The half of a transport the client writes its commands to
+pub type TransportSink = Pin<Box<dyn Sink<Message, Error = HassError> + Send>>;
+
+/// The half of a transport the client reads the messages of Home Assistant from
+pub type TransportStream = Pin<Box<dyn Stream<Item = HassResult<Message>> + Send>>;
+
+/// A byte stream carrying a websocket, like TCP, a proxy tunnel or TLS
+pub(crate) trait ByteStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> ByteStream for S {}
+
+/// Opens a TCP connection to the host of a url
+///
+/// The host string of a url keeps the brackets of IPv6 addresses, which can
+/// not be resolved, so the address is used instead.
+pub(crate) async fn connect_tcp(host: &Host<&str>, port: u16) -> std::io::Result<TcpStream> {
+    match host {
+        Host::Domain(domain) => TcpStream::connect((*domain, port)).await,
+        Host::Ipv4(addr) => TcpStream::connect((*addr, port)).await,
+        Host::Ipv6(addr) => TcpStream::connect((*addr, port)).await,
+    }
+}
+
+/// A connection carrying websocket messages between the client and Home Assistant
+///
+/// Implemented for tungstenite websockets over any stream, like TCP, TLS or
+/// Unix domain sockets, and for the in-memory [`MemoryTransport`]. Use
+/// [`crate::HaClient::connect_with`] to connect over a transport.
+pub trait Transport {
+    /// Splits the transport into the halves used by the sender and receiver loops
+    fn split(self) -> (TransportSink, TransportStream);
+}
+
+impl<S> Transport for WebSocketStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    fn split(self) -> (TransportSink, TransportStream) {
+        let (sink, stream) = StreamExt::split(self);
+        (
+            Box::pin(sink.sink_map_err(HassError::from)),
+            Box::pin(stream.map(|message| message.map_err(HassError::from))),
+        )
+    }
+}
+
+/// One end of an in-memory transport, used to run the client without a network
+///
+/// ```
+/// # async fn example() {
+/// use r_hassclient::transport::MemoryTransport;
+/// use tokio_tungstenite::tungstenite::Message;
+///
+/// let (client, mut server) = MemoryTransport::pair();
+/// client.send(Message::Text("ping".to_owned())).unwrap();
+/// assert_eq!(server.recv().await, Some(Message::Text("ping".to_owned())));
+/// # }
+/// ```
+pub struct MemoryTransport {
+    to_peer: UnboundedSender<Message>,
+    from_peer: UnboundedReceiver<Message>,
+}
+
+impl MemoryTransport {
+    /// Creates two connected ends, the messages sent on one end are received on the other
+    pub fn pair() -> (MemoryTransport, MemoryTransport) {
+        let (to_server, from_client) = mpsc::unbounded_channel();
+        let (to_client, from_server) = mpsc::unbounded_channel();
+        (
+            MemoryTransport {
+                to_peer: to_server,
+                from_peer: from_server,
+            },
+            MemoryTransport {
+                to_peer: to_client,
+                from_peer: from_client,
+            },
+        )
+    }
+
+    /// Sends a message to the other end
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the other end is dropped.
+    pub fn send(&self, message: Message) -> HassResult<()> {
+        self.to_peer
+            .send(message)
+            .map_err(|_| HassError::ConnectionError)
+    }
+
+    /// Receives the next message, `None` when the other end is dropped
+    pub async fn recv(&mut self) -> Option<Message> {
+        self.from_peer.recv().await
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn split(self) -> (TransportSink, TransportStream) {
+        let sink = sink::unfold(self.to_peer, |to_peer, message| async move {
+            to_peer
+                .send(message)
+                .map_err(|_| HassError::ConnectionError)?;
+            Ok(to_peer)
+        });
+        let stream = stream::unfold(self.from_peer, |mut from_peer| async move {
+            let message = from_peer.recv().await?;
+            Some((Ok(message), from_peer))
+        });
+        (Box::pin(sink), Box::pin(stream))
+    }
+}
@@ -0,0 +1,79 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use percent_encoding::percent_decode_str;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use url::{Host, Url};
+
+use super::connect_tcp;
+use crate::{auth::Secret, HassError, HassResult};
+
+/// The longest response head accepted from a proxy
+const MAX_RESPONSE_HEAD: usize = 8 * 1024;
+
+/// Opens a tunnel to the host and port through a HTTP CONNECT proxy
+///
+/// The user and password of the proxy url are sent with basic authentication.
+pub(crate) async fn connect_through(
+    proxy: &Url,
+    host: &Host<&str>,
+    port: u16,
+) -> HassResult<TcpStream> {
+    if proxy.scheme() != "http" {
+        return Err(proxy_error(format!(
+            "unsupported proxy scheme {}",
+            proxy.scheme()
+        )));
+    }
+    let proxy_host = proxy
+        .host()
+        .ok_or_else(|| proxy_error(format!("no host in {}", proxy)))?;
+    let proxy_port = proxy.port_or_known_default().unwrap_or(80);
+    let mut stream = connect_tcp(&proxy_host, proxy_port)
+        .await
+        .map_err(proxy_error)?;
+
+    // Only the authority of the CONNECT line writes IPv6 addresses in brackets
+    let target = format!("{}:{}", host, port);
+    let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+    if !proxy.username().is_empty() {
+        let credentials = Secret::new(format!(
+            "{}:{}",
+            percent_decode_str(proxy.username()).decode_utf8_lossy(),
+            percent_decode_str(proxy.password().unwrap_or_default()).decode_utf8_lossy()
+        ));
+        request.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            STANDARD.encode(credentials.expose())
+        ));
+    }
+    request.push_str("\r\n");
+    let request = Secret::new(request);
+    stream
+        .write_all(request.expose().as_bytes())
+        .await
+        .map_err(proxy_error)?;
+
+    // Read byte by byte, the bytes after the head belong to the tunnel
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_RESPONSE_HEAD {
+            return Err(proxy_error("the response of the proxy is too long"));
+        }
+        head.push(stream.read_u8().await.map_err(proxy_error)?);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let status_line = head.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(stream),
+        _ => Err(proxy_error(format!(
+            "the proxy refused the tunnel to {}: {}",
+            target, status_line
+        ))),
+    }
+}
+
+fn proxy_error(error: impl std::fmt::Display) -> HassError {
+    HassError::GenericError(format!("Proxy error: {}", error))
+}
@@ -0,0 +1,316 @@
+use futures_util::{future, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, LineWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::oneshot,
+    task::JoinHandle,
+    time::{sleep_until, Instant},
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::{MemoryTransport, Transport, TransportSink, TransportStream};
+use crate::{auth::REDACTED, HassError, HassResult};
+
+/// The direction of a recorded frame, seen from the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// A text frame in a recorded session, one per line in the recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Milliseconds since the start of the session
+    pub at: u64,
+    pub direction: Direction,
+    pub text: String,
+}
+
+/// Records every text frame of the inner transport to a JSONL file
+///
+/// Access tokens in the sent frames are redacted, so recordings can be shared.
+///
+/// ```no_run
+/// # async fn example(url: url::Url) -> r_hassclient::HassResult<()> {
+/// use r_hassclient::{transport::RecordingTransport, HaClient};
+///
+/// let (ws, _) = tokio_tungstenite::connect_async(url).await?;
+/// let transport = RecordingTransport::new(ws, "session.jsonl")?;
+/// let mut conn = HaClient::builder().build().connect_with(transport).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RecordingTransport<T> {
+    inner: T,
+    recorder: Arc<Recorder>,
+}
+
+struct Recorder {
+    start: Instant,
+    file: Mutex<LineWriter<File>>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    /// Wraps the transport, the file is created or truncated
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can not be created.
+    pub fn new(inner: T, path: impl AsRef<Path>) -> HassResult<RecordingTransport<T>> {
+        let file = File::create(path).map_err(|err| HassError::GenericError(err.to_string()))?;
+        Ok(RecordingTransport {
+            inner,
+            recorder: Arc::new(Recorder {
+                start: Instant::now(),
+                file: Mutex::new(LineWriter::new(file)),
+            }),
+        })
+    }
+}
+
+impl Recorder {
+    fn record(&self, direction: Direction, message: &Message) {
+        let Message::Text(text) = message else {
+            return;
+        };
+        let text = match direction {
+            Direction::Sent => redact(text),
+            Direction::Received => text.clone(),
+        };
+        let frame = RecordedFrame {
+            at: self.start.elapsed().as_millis() as u64,
+            direction,
+            text,
+        };
+        if let Ok(line) = serde_json::to_string(&frame) {
+            let mut file = self
+                .file
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            // A recording is a debugging aid, it never breaks the connection
+            _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+impl<T: Transport> Transport for RecordingTransport<T> {
+    fn split(self) -> (TransportSink, TransportStream) {
+        let (sink, stream) = self.inner.split();
+        let sent = Arc::clone(&self.recorder);
+        let received = self.recorder;
+        let sink = sink.with(move |message: Message| {
+            sent.record(Direction::Sent, &message);
+            future::ready(Ok::<_, HassError>(message))
+        });
+        let stream = stream.inspect(move |message| {
+            if let Ok(message) = message {
+                received.record(Direction::Received, message);
+            }
+        });
+        (Box::pin(sink), Box::pin(stream))
+    }
+}
+
+/// How fast the received frames of a recording are replayed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReplaySpeed {
+    /// With the delays of the recording
+    RealTime,
+    /// With the delays of the recording divided by the factor
+    Accelerated(f64),
+    /// Without delays
+    #[default]
+    Immediate,
+}
+
+impl ReplaySpeed {
+    fn delay(&self, at: u64) -> Duration {
+        let at = Duration::from_millis(at);
+        match self {
+            ReplaySpeed::RealTime => at,
+            ReplaySpeed::Accelerated(factor) if *factor > 0.0 => at.div_f64(*factor),
+            ReplaySpeed::Accelerated(_) | ReplaySpeed::Immediate => Duration::ZERO,
+        }
+    }
+}
+
+/// Replays a recorded session to the client
+///
+/// The received frames are sent to the client in the order of the recording.
+/// A received frame recorded after a sent frame is only replayed once the
+/// client sent that frame, and every frame the client sends must match the
+/// recording, ignoring the access token. A frame sent after the end of the
+/// recording fails the replay.
+///
+/// ```no_run
+/// # async fn example() -> r_hassclient::HassResult<()> {
+/// use r_hassclient::{transport::{Replay, ReplaySpeed}, HaClient};
+///
+/// let (transport, replay) = Replay::load("session.jsonl")?
+///     .speed(ReplaySpeed::Accelerated(10.0))
+///     .start();
+/// let mut conn = HaClient::builder().build().connect_with(transport).await?;
+/// conn.authenticate_with_token("token").await?;
+/// conn.ping().await?;
+/// replay.finish().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Replay {
+    frames: Vec<RecordedFrame>,
+    speed: ReplaySpeed,
+}
+
+/// A running replay, used to check the client sent every recorded frame
+pub struct ReplayHandle {
+    driver: JoinHandle<HassResult<()>>,
+    finished: oneshot::Sender<()>,
+}
+
+impl Replay {
+    pub fn new(frames: Vec<RecordedFrame>) -> Replay {
+        Replay {
+            frames,
+            speed: ReplaySpeed::default(),
+        }
+    }
+
+    /// Reads a recording made by [`RecordingTransport`]
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can not be read or a
+    /// line is not a recorded frame.
+    pub fn load(path: impl AsRef<Path>) -> HassResult<Replay> {
+        let file = File::open(path).map_err(|err| HassError::GenericError(err.to_string()))?;
+        let frames = BufReader::new(file)
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|line| {
+                let line = line.map_err(|err| HassError::GenericError(err.to_string()))?;
+                serde_json::from_str(&line).map_err(HassError::from)
+            })
+            .collect::<HassResult<Vec<RecordedFrame>>>()?;
+        Ok(Replay::new(frames))
+    }
+
+    pub fn speed(mut self, speed: ReplaySpeed) -> Replay {
+        self.speed = speed;
+        self
+    }
+
+    /// Starts the replay, the client connects with the returned transport
+    pub fn start(self) -> (MemoryTransport, ReplayHandle) {
+        let (client, server) = MemoryTransport::pair();
+        let (finished, finishing) = oneshot::channel();
+        let driver = tokio::spawn(drive(self.frames, self.speed, server, finishing));
+        (client, ReplayHandle { driver, finished })
+    }
+}
+
+impl ReplayHandle {
+    /// Waits until all recorded frames are replayed
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a frame sent by the client does
+    /// not match the recording, the client sent a frame after the end of the
+    /// recording, or the client disconnected before sending all recorded frames.
+    pub async fn finish(self) -> HassResult<()> {
+        // The driver is already gone when the replay failed
+        _ = self.finished.send(());
+        self.driver
+            .await
+            .map_err(|err| HassError::GenericError(err.to_string()))?
+    }
+}
+
+async fn drive(
+    frames: Vec<RecordedFrame>,
+    speed: ReplaySpeed,
+    mut server: MemoryTransport,
+    finishing: oneshot::Receiver<()>,
+) -> HassResult<()> {
+    let start = Instant::now();
+    let count = frames.len();
+    for (index, frame) in frames.into_iter().enumerate() {
+        match frame.direction {
+            Direction::Received => {
+                sleep_until(start + speed.delay(frame.at)).await;
+                server.send(Message::Text(frame.text))?;
+            }
+            Direction::Sent => {
+                let sent = match server.recv().await {
+                    Some(Message::Text(text)) => text,
+                    Some(message) => message.to_string(),
+                    None => {
+                        return Err(HassError::GenericError(format!(
+                            "the client disconnected before sending frame {}: {}",
+                            index + 1,
+                            frame.text
+                        )))
+                    }
+                };
+                if !same_frame(&frame.text, &sent) {
+                    return Err(HassError::GenericError(format!(
+                        "frame {} does not match the recording, expected {} but the client sent {}",
+                        index + 1,
+                        frame.text,
+                        sent
+                    )));
+                }
+            }
+        }
+    }
+
+    // Until the replay is finished, the client must not send more than the recording
+    tokio::select! {
+        biased;
+        extra = server.recv() => match extra {
+            Some(message) => Err(HassError::GenericError(format!(
+                "the client sent frame {} after the end of the recording: {}",
+                count + 1,
+                message
+            ))),
+            None => Ok(()),
+        },
+        _ = finishing => Ok(()),
+    }
+}
+
+fn same_frame(recorded: &str, sent: &str) -> bool {
+    match (
+        serde_json::from_str::<Value>(recorded),
+        serde_json::from_str::<Value>(sent),
+    ) {
+        (Ok(recorded), Ok(sent)) => without_token(recorded) == without_token(sent),
+        _ => recorded == sent,
+    }
+}
+
+fn without_token(mut frame: Value) -> Value {
+    if let Some(frame) = frame.as_object_mut() {
+        frame.remove("access_token");
+    }
+    frame
+}
+
+fn redact(text: &str) -> String {
+    match serde_json::from_str::<Value>(text) {
+        Ok(Value::Object(mut frame)) if frame.contains_key("access_token") => {
+            frame.insert("access_token".to_owned(), Value::from(REDACTED));
+            Value::Object(frame).to_string()
+        }
+        _ => text.to_owned(),
+    }
+}
@@ -0,0 +1,27 @@
+use futures_util::{SinkExt, StreamExt};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::{MemoryTransport, Transport};
+
+#[tokio::test]
+async fn memory_transport_should_carry_messages_both_ways() {
+    let (client, mut server) = MemoryTransport::pair();
+    let (mut sink, mut stream) = client.split();
+
+    sink.send(Message::Text("ping".to_owned())).await.unwrap();
+    assert_eq!(server.recv().await, Some(Message::Text("ping".to_owned())));
+
+    server.send(Message::Text("pong".to_owned())).unwrap();
+    let message = stream.next().await.unwrap().unwrap();
+    assert_eq!(message, Message::Text("pong".to_owned()));
+}
+
+#[tokio::test]
+async fn memory_transport_should_end_when_peer_is_dropped() {
+    let (client, server) = MemoryTransport::pair();
+    let (mut sink, mut stream) = client.split();
+    drop(server);
+
+    assert!(stream.next().await.is_none());
+    assert!(sink.send(Message::Text("ping".to_owned())).await.is_err());
+}
@@ -2,15 +2,22 @@ use serde::Serialize;
 use serde_json::Value;
 use tokio_tungstenite::tungstenite::Message;
 
+use crate::auth::Secret;
+
 // Todo: these warnings is probably due to bad visibility that I do not really
 // understand yet :)
 #[derive(Debug)]
 pub(crate) enum HaCommand {
     AuthInfo(Auth),
     Ping(Ask),
+    GetStates(Ask),
+    GetServices(Ask),
+    ListRegistry(Ask),
     SubscribeEvent(Subscribe),
+    Unsubscribe(Unsubscribe),
     CallService(CallService),
     CreateHelper(CreateHelperCommand),
+    SupportedFeatures(SupportedFeatures),
 }
 
 impl HaCommand {
@@ -28,22 +35,26 @@ impl HaCommand {
                 let cmd_str = serde_json::to_string(&subscribe).unwrap();
                 Message::Text(cmd_str)
             }
-            // Self::Unsubscribe(unsubscribe) => {
-            //     let cmd_str = serde_json::to_string(&unsubscribe).unwrap();
-            //     TungsteniteMessage::Text(cmd_str)
-            // }
+            Self::Unsubscribe(unsubscribe) => {
+                let cmd_str = serde_json::to_string(&unsubscribe).unwrap();
+                Message::Text(cmd_str)
+            }
             // Self::GetConfig(getconfig) => {
             //     let cmd_str = serde_json::to_string(&getconfig).unwrap();
             //     TungsteniteMessage::Text(cmd_str)
             // }
-            // Self::GetStates(getstates) => {
-            //     let cmd_str = serde_json::to_string(&getstates).unwrap();
-            //     TungsteniteMessage::Text(cmd_str)
-            // }
-            // Self::GetServices(getservices) => {
-            //     let cmd_str = serde_json::to_string(&getservices).unwrap();
-            //     TungsteniteMessage::Text(cmd_str)
-            // }
+            Self::GetStates(getstates) => {
+                let cmd_str = serde_json::to_string(&getstates).unwrap();
+                Message::Text(cmd_str)
+            }
+            Self::GetServices(getservices) => {
+                let cmd_str = serde_json::to_string(&getservices).unwrap();
+                Message::Text(cmd_str)
+            }
+            Self::ListRegistry(listregistry) => {
+                let cmd_str = serde_json::to_string(&listregistry).unwrap();
+                Message::Text(cmd_str)
+            }
             // Self::GetPanels(getpanels) => {
             //     let cmd_str = serde_json::to_string(&getpanels).unwrap();
             //     TungsteniteMessage::Text(cmd_str)
@@ -56,7 +67,11 @@ impl HaCommand {
             Self::CreateHelper(create_helper_command) => {
                 let cmd_str = serde_json::to_string(&create_helper_command).unwrap();
                 Message::Text(cmd_str)
-            } 
+            }
+            Self::SupportedFeatures(supported_features) => {
+                let cmd_str = serde_json::to_string(&supported_features).unwrap();
+                Message::Text(cmd_str)
+            }
         }
     }
 }
@@ -65,7 +80,7 @@ impl HaCommand {
 pub(crate) struct Auth {
     #[serde(rename = "type")]
     pub(crate) msg_type: String,
-    pub(crate) access_token: String,
+    pub(crate) access_token: Secret,
 }
 //used to fetch from server
 #[derive(Debug, Serialize, PartialEq)]
@@ -80,7 +95,17 @@ pub(crate) struct Subscribe {
     pub(crate) id: Option<u64>,
     #[serde(rename = "type")]
     pub(crate) msg_type: String,
-    pub(crate) event_type: String,
+    // without event type Home Assistant sends all events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) event_type: Option<String>,
+}
+//used to cancel an Event subscribtion
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct Unsubscribe {
+    pub(crate) id: Option<u64>,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    pub(crate) subscription: u64,
 }
 #[derive(Debug, Serialize, PartialEq)]
 pub(crate) struct CallService {
@@ -90,6 +115,8 @@ pub(crate) struct CallService {
     pub(crate) domain: String,
     pub(crate) service: String,
     pub(crate) service_data: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) target: Option<Value>,
 }
 
 #[derive(Debug, Serialize, PartialEq)]
@@ -100,9 +127,26 @@ pub(crate) struct CreateHelperCommand {
     pub(crate) name: String,
 }
 
+// Kept for the commented out `get_config` of the connection
+#[allow(dead_code)]
 #[derive(Debug, Serialize, PartialEq)]
 pub(crate) struct GetConfig {
     pub(crate) id: Option<u64>,
     #[serde(rename = "type")]
     pub(crate) msg_type: String,
 }
+
+//used to enable optional features of the websocket api after authentication
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct SupportedFeatures {
+    pub(crate) id: Option<u64>,
+    #[serde(rename = "type")]
+    pub(crate) msg_type: String,
+    pub(crate) features: Features,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct Features {
+    // Home Assistant batches messages into a json array when set to 1
+    pub(crate) coalesce_messages: u8,
+}
@@ -0,0 +1,536 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{codegen::EntityId, HaState, HassError, HassResult};
+
+/// The state of entities that only know about being on or off, like lights,
+/// switches, fans and binary sensors.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnOff {
+    On,
+    Off,
+    Unavailable,
+    #[serde(other)]
+    Unknown,
+}
+
+impl OnOff {
+    pub fn is_on(&self) -> bool {
+        *self == OnOff::On
+    }
+}
+
+/// The color modes a light can be in, or support.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    Onoff,
+    Brightness,
+    ColorTemp,
+    Hs,
+    Xy,
+    Rgb,
+    Rgbw,
+    Rgbww,
+    White,
+    #[serde(other)]
+    Unknown,
+}
+
+/// The state of a climate entity is its current hvac mode.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HvacMode {
+    Off,
+    Heat,
+    Cool,
+    HeatCool,
+    Auto,
+    Dry,
+    FanOnly,
+    Unavailable,
+    #[serde(other)]
+    Unknown,
+}
+
+impl HvacMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HvacMode::Off => "off",
+            HvacMode::Heat => "heat",
+            HvacMode::Cool => "cool",
+            HvacMode::HeatCool => "heat_cool",
+            HvacMode::Auto => "auto",
+            HvacMode::Dry => "dry",
+            HvacMode::FanOnly => "fan_only",
+            HvacMode::Unavailable => "unavailable",
+            HvacMode::Unknown => "unknown",
+        }
+    }
+}
+
+/// What the climate device is actually doing right now.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HvacAction {
+    Off,
+    Preheating,
+    Heating,
+    Cooling,
+    Drying,
+    Idle,
+    Fan,
+    Defrosting,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverStatus {
+    Open,
+    Closed,
+    Opening,
+    Closing,
+    Unavailable,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaPlayerStatus {
+    Off,
+    On,
+    Idle,
+    Playing,
+    Paused,
+    Standby,
+    Buffering,
+    Unavailable,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LockStatus {
+    Locked,
+    Unlocked,
+    Locking,
+    Unlocking,
+    Jammed,
+    Open,
+    Opening,
+    Unavailable,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Typed view of a `light` entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LightState {
+    pub entity_id: String,
+    pub state: OnOff,
+    pub attributes: LightAttributes,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LightAttributes {
+    pub friendly_name: Option<String>,
+    pub brightness: Option<u8>,
+    pub color_mode: Option<ColorMode>,
+    pub supported_color_modes: Option<Vec<ColorMode>>,
+    pub color_temp_kelvin: Option<u32>,
+    pub min_color_temp_kelvin: Option<u32>,
+    pub max_color_temp_kelvin: Option<u32>,
+    pub hs_color: Option<(f32, f32)>,
+    pub rgb_color: Option<(u8, u8, u8)>,
+    pub xy_color: Option<(f32, f32)>,
+    pub effect: Option<String>,
+    pub effect_list: Option<Vec<String>>,
+    pub supported_features: Option<u32>,
+}
+
+/// Typed view of a `climate` entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClimateState {
+    pub entity_id: String,
+    pub state: HvacMode,
+    pub attributes: ClimateAttributes,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ClimateAttributes {
+    pub friendly_name: Option<String>,
+    pub hvac_modes: Option<Vec<HvacMode>>,
+    pub hvac_action: Option<HvacAction>,
+    pub current_temperature: Option<f64>,
+    pub temperature: Option<f64>,
+    pub target_temp_high: Option<f64>,
+    pub target_temp_low: Option<f64>,
+    pub target_temp_step: Option<f64>,
+    pub min_temp: Option<f64>,
+    pub max_temp: Option<f64>,
+    pub current_humidity: Option<f64>,
+    pub humidity: Option<f64>,
+    pub preset_mode: Option<String>,
+    pub preset_modes: Option<Vec<String>>,
+    pub fan_mode: Option<String>,
+    pub fan_modes: Option<Vec<String>>,
+    pub swing_mode: Option<String>,
+    pub swing_modes: Option<Vec<String>>,
+    pub supported_features: Option<u32>,
+}
+
+/// Typed view of a `cover` entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CoverState {
+    pub entity_id: String,
+    pub state: CoverStatus,
+    pub attributes: CoverAttributes,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CoverAttributes {
+    pub friendly_name: Option<String>,
+    pub device_class: Option<String>,
+    pub current_position: Option<u8>,
+    pub current_tilt_position: Option<u8>,
+    pub supported_features: Option<u32>,
+}
+
+/// Typed view of a `media_player` entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MediaPlayerState {
+    pub entity_id: String,
+    pub state: MediaPlayerStatus,
+    pub attributes: MediaPlayerAttributes,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MediaPlayerAttributes {
+    pub friendly_name: Option<String>,
+    pub device_class: Option<String>,
+    pub volume_level: Option<f64>,
+    pub is_volume_muted: Option<bool>,
+    pub media_content_id: Option<String>,
+    pub media_content_type: Option<String>,
+    pub media_duration: Option<f64>,
+    pub media_position: Option<f64>,
+    pub media_title: Option<String>,
+    pub media_artist: Option<String>,
+    pub media_album_name: Option<String>,
+    pub app_name: Option<String>,
+    pub source: Option<String>,
+    pub source_list: Option<Vec<String>>,
+    pub sound_mode: Option<String>,
+    pub sound_mode_list: Option<Vec<String>>,
+    pub shuffle: Option<bool>,
+    pub repeat: Option<String>,
+    pub entity_picture: Option<String>,
+    pub supported_features: Option<u32>,
+}
+
+/// Typed view of a `sensor` entity
+///
+/// The state of a sensor is always sent as a string from Home Assistant, use
+/// [`SensorState::value`] to get the numeric value.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SensorState {
+    pub entity_id: String,
+    pub state: String,
+    pub attributes: SensorAttributes,
+}
+
+impl SensorState {
+    /// The numeric value of the sensor, `None` if the sensor is not numeric or unavailable
+    pub fn value(&self) -> Option<f64> {
+        self.state.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SensorAttributes {
+    pub friendly_name: Option<String>,
+    pub unit_of_measurement: Option<String>,
+    pub device_class: Option<String>,
+    pub state_class: Option<String>,
+}
+
+/// Typed view of a `binary_sensor` entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BinarySensorState {
+    pub entity_id: String,
+    pub state: OnOff,
+    pub attributes: DeviceClassAttributes,
+}
+
+/// Typed view of a `switch` entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SwitchState {
+    pub entity_id: String,
+    pub state: OnOff,
+    pub attributes: DeviceClassAttributes,
+}
+
+/// Attributes of entities that only carries a name and a device class
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DeviceClassAttributes {
+    pub friendly_name: Option<String>,
+    pub device_class: Option<String>,
+}
+
+/// Typed view of a `lock` entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LockState {
+    pub entity_id: String,
+    pub state: LockStatus,
+    pub attributes: LockAttributes,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LockAttributes {
+    pub friendly_name: Option<String>,
+    pub changed_by: Option<String>,
+    pub code_format: Option<String>,
+    pub supported_features: Option<u32>,
+}
+
+/// Typed view of a `fan` entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FanState {
+    pub entity_id: String,
+    pub state: OnOff,
+    pub attributes: FanAttributes,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FanAttributes {
+    pub friendly_name: Option<String>,
+    pub percentage: Option<u8>,
+    pub percentage_step: Option<f64>,
+    pub preset_mode: Option<String>,
+    pub preset_modes: Option<Vec<String>>,
+    pub oscillating: Option<bool>,
+    pub direction: Option<String>,
+    pub supported_features: Option<u32>,
+}
+
+/// Typed view of an `input_boolean` helper
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InputBooleanState {
+    pub entity_id: String,
+    pub state: OnOff,
+    pub attributes: InputHelperAttributes,
+}
+
+/// Typed view of an `input_button` helper, the state is the time it was last pressed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InputButtonState {
+    pub entity_id: String,
+    pub state: String,
+    pub attributes: InputHelperAttributes,
+}
+
+/// Attributes shared by all the input helpers
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct InputHelperAttributes {
+    pub friendly_name: Option<String>,
+    pub icon: Option<String>,
+    pub editable: Option<bool>,
+}
+
+/// Typed view of an `input_number` helper
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InputNumberState {
+    pub entity_id: String,
+    pub state: String,
+    pub attributes: InputNumberAttributes,
+}
+
+impl InputNumberState {
+    /// The numeric value of the helper, `None` if it is unavailable
+    pub fn value(&self) -> Option<f64> {
+        self.state.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct InputNumberAttributes {
+    pub friendly_name: Option<String>,
+    pub icon: Option<String>,
+    pub editable: Option<bool>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub step: Option<f64>,
+    pub mode: Option<String>,
+    pub unit_of_measurement: Option<String>,
+}
+
+/// Typed view of an `input_select` helper, the state is the selected option
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InputSelectState {
+    pub entity_id: String,
+    pub state: String,
+    pub attributes: InputSelectAttributes,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct InputSelectAttributes {
+    pub friendly_name: Option<String>,
+    pub icon: Option<String>,
+    pub editable: Option<bool>,
+    pub options: Vec<String>,
+}
+
+/// Typed view of an `input_text` helper
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InputTextState {
+    pub entity_id: String,
+    pub state: String,
+    pub attributes: InputTextAttributes,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct InputTextAttributes {
+    pub friendly_name: Option<String>,
+    pub icon: Option<String>,
+    pub editable: Option<bool>,
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+    pub pattern: Option<String>,
+    pub mode: Option<String>,
+}
+
+/// Typed view of an `input_datetime` helper
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InputDatetimeState {
+    pub entity_id: String,
+    pub state: String,
+    pub attributes: InputDatetimeAttributes,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct InputDatetimeAttributes {
+    pub friendly_name: Option<String>,
+    pub icon: Option<String>,
+    pub editable: Option<bool>,
+    pub has_date: Option<bool>,
+    pub has_time: Option<bool>,
+    pub year: Option<i32>,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
+    pub hour: Option<u32>,
+    pub minute: Option<u32>,
+    pub second: Option<u32>,
+    pub timestamp: Option<f64>,
+}
+
+impl HaState {
+    /// The domain of the entity, i.e. `light` for `light.kitchen`
+    pub fn domain(&self) -> &str {
+        EntityId::domain_of(&self.entity_id)
+    }
+
+    pub fn as_light(&self) -> HassResult<LightState> {
+        self.as_domain("light")
+    }
+
+    pub fn as_climate(&self) -> HassResult<ClimateState> {
+        self.as_domain("climate")
+    }
+
+    pub fn as_cover(&self) -> HassResult<CoverState> {
+        self.as_domain("cover")
+    }
+
+    pub fn as_media_player(&self) -> HassResult<MediaPlayerState> {
+        self.as_domain("media_player")
+    }
+
+    pub fn as_sensor(&self) -> HassResult<SensorState> {
+        self.as_domain("sensor")
+    }
+
+    pub fn as_binary_sensor(&self) -> HassResult<BinarySensorState> {
+        self.as_domain("binary_sensor")
+    }
+
+    pub fn as_switch(&self) -> HassResult<SwitchState> {
+        self.as_domain("switch")
+    }
+
+    pub fn as_lock(&self) -> HassResult<LockState> {
+        self.as_domain("lock")
+    }
+
+    pub fn as_fan(&self) -> HassResult<FanState> {
+        self.as_domain("fan")
+    }
+
+    pub fn as_input_boolean(&self) -> HassResult<InputBooleanState> {
+        self.as_domain("input_boolean")
+    }
+
+    pub fn as_input_button(&self) -> HassResult<InputButtonState> {
+        self.as_domain("input_button")
+    }
+
+    pub fn as_input_number(&self) -> HassResult<InputNumberState> {
+        self.as_domain("input_number")
+    }
+
+    pub fn as_input_select(&self) -> HassResult<InputSelectState> {
+        self.as_domain("input_select")
+    }
+
+    pub fn as_input_text(&self) -> HassResult<InputTextState> {
+        self.as_domain("input_text")
+    }
+
+    pub fn as_input_datetime(&self) -> HassResult<InputDatetimeState> {
+        self.as_domain("input_datetime")
+    }
+
+    /// Converts the state to a typed view if the entity belongs to the domain
+    ///
+    /// # Errors
+    ///
+    /// Returns `DomainMismatch` if the entity is from another domain and
+    /// `UnableToDeserialize` if the state or attributes do not match the view.
+    fn as_domain<T: DeserializeOwned>(&self, domain: &str) -> HassResult<T> {
+        if self.domain() != domain {
+            return Err(HassError::DomainMismatch {
+                expected: domain.to_owned(),
+                entity_id: self.entity_id.clone(),
+            });
+        }
+        let attributes = self
+            .attributes
+            .as_ref()
+            .map(|attributes| Value::Object(attributes.clone().into_iter().collect()))
+            .unwrap_or_else(|| Value::Object(Default::default()));
+
+        let state = serde_json::json!({
+            "entity_id": self.entity_id,
+            "state": self.state,
+            "attributes": attributes,
+        });
+        Ok(T::deserialize(state)?)
+    }
+}
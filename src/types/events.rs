@@ -1,9 +1,10 @@
-use serde::{Deserialize, Deserializer, Serialize};
-use std::fmt;
-
-use std::collections::HashMap;
-
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{value::RawValue, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, OnceLock},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 // #[serde(tag = "event_type", content = "data")]
@@ -12,27 +13,102 @@ pub enum HaEventData {
     StateChangedEvent(StateChangedEvent),
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+/// An event fired in Home Assistant
+///
+/// The data of the event is kept as raw json and only decoded when it is read.
+/// Clones share the data, so an event passed to many callbacks is decoded once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawEvent", into = "RawEvent")]
 pub struct HaEvent {
-    // #[serde(deserialize_with = "deserialize_my_data")]
-    pub data: Value,
     pub event_type: String,
     pub time_fired: String,
     pub origin: String,
-    // pub context: Context,
+    data: Arc<EventData>,
+}
+
+#[derive(Debug)]
+struct EventData {
+    raw: Box<RawValue>,
+    // the typed data, decoded by the first call to `event_data`
+    typed: OnceLock<Result<HaEventData, serde_json::Error>>,
+}
+
+// The event as it is sent by Home Assistant
+#[derive(Serialize, Deserialize)]
+struct RawEvent {
+    data: Box<RawValue>,
+    event_type: String,
+    time_fired: String,
+    origin: String,
+}
+
+impl From<RawEvent> for HaEvent {
+    fn from(event: RawEvent) -> Self {
+        HaEvent {
+            event_type: event.event_type,
+            time_fired: event.time_fired,
+            origin: event.origin,
+            data: Arc::new(EventData {
+                raw: event.data,
+                typed: OnceLock::new(),
+            }),
+        }
+    }
+}
+
+impl From<HaEvent> for RawEvent {
+    fn from(event: HaEvent) -> Self {
+        RawEvent {
+            data: event.data.raw.clone(),
+            event_type: event.event_type,
+            time_fired: event.time_fired,
+            origin: event.origin,
+        }
+    }
+}
+
+impl PartialEq for HaEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.event_type == other.event_type
+            && self.time_fired == other.time_fired
+            && self.origin == other.origin
+            && self.data.raw.get() == other.data.raw.get()
+    }
 }
 
 impl HaEvent {
+    /// A copy of the typed data of the event, see [`HaEvent::event_data`] to borrow it
     pub fn get_event_data(&self) -> Result<HaEventData, serde_json::Error> {
+        match self.event_data() {
+            Ok(data) => Ok(data.clone()),
+            // The error can not be cloned, decoding again returns the same error
+            Err(_) => self.decode(),
+        }
+    }
+
+    /// The typed data of the event, decoded once and shared by all clones
+    pub fn event_data(&self) -> Result<&HaEventData, &serde_json::Error> {
+        self.data.typed.get_or_init(|| self.decode()).as_ref()
+    }
+
+    fn decode(&self) -> Result<HaEventData, serde_json::Error> {
         match self.event_type.as_str() {
             "state_changed" => {
-                let data = StateChangedEvent::deserialize(self.data.clone())
-                    .map_err(serde::de::Error::custom)?;
-                Ok(HaEventData::StateChangedEvent(data))
+                serde_json::from_str(self.data.raw.get()).map(HaEventData::StateChangedEvent)
             }
             _ => Err(serde::de::Error::custom("unknown type")),
         }
     }
+
+    /// The data of the event as raw json
+    pub fn raw_data(&self) -> &RawValue {
+        &self.data.raw
+    }
+
+    /// Decodes the data of the event, used for events without a typed variant
+    pub fn data_as<'a, T: Deserialize<'a>>(&'a self) -> Result<T, serde_json::Error> {
+        serde_json::from_str(self.data.raw.get())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -43,11 +119,25 @@ pub struct StateChangedEvent {
     pub old_state: Option<HaState>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct HaState {
     pub entity_id: String,
     pub attributes: Option<HashMap<String, Value>>,
     pub state: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_changed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_updated: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<Context>,
+}
+
+/// Identifies what caused a state change, like a service call or an automation
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Context {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub user_id: Option<String>,
 }
 
 impl fmt::Display for StateChangedEvent {
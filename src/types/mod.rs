@@ -1,12 +1,18 @@
 mod commands;
 mod config;
+mod domains;
 mod events;
+mod registry;
 mod responses;
+mod service_descriptions;
 
 #[cfg(test)]
 mod tests;
 
 pub(crate) use commands::*;
 pub use config::*;
+pub use domains::*;
 pub use events::*;
+pub use registry::*;
 pub use responses::*;
+pub use service_descriptions::*;
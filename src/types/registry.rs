@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// An entry in the entity registry
+///
+/// Only entities with a unique id are in the registry. The area is `None` if
+/// the entity uses the area of its device.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EntityRegistryEntry {
+    pub entity_id: String,
+    pub name: Option<String>,
+    pub platform: String,
+    pub area_id: Option<String>,
+    pub device_id: Option<String>,
+    pub labels: Vec<String>,
+    pub disabled_by: Option<String>,
+    pub hidden_by: Option<String>,
+}
+
+/// An entry in the device registry
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DeviceRegistryEntry {
+    pub id: String,
+    pub name: Option<String>,
+    pub name_by_user: Option<String>,
+    pub area_id: Option<String>,
+    pub labels: Vec<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+}
@@ -1,11 +1,12 @@
-// use serde_json::Value;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use serde_json::{value::RawValue, Value};
+use std::{borrow::Cow, collections::HashMap, fmt};
 
-use crate::HaEvent;
+use crate::{HaEvent, HassError, HassResult};
 
-#[derive(Debug, Deserialize)]
-#[serde(tag = "type")]
-#[serde(rename_all = "snake_case")]
+// The payloads of the auth messages are only kept for debugging purposes
+#[allow(dead_code)]
+#[derive(Debug)]
 pub(crate) enum Response {
     AuthRequired(AuthRequired),
     AuthOk(AuthOk),
@@ -13,10 +14,73 @@ pub(crate) enum Response {
     Event(WsEvent),
     Result(WsResult),
     Pong(WSPong),
-    #[serde(other)]
     Unknown,
 }
 
+// Not derived with an internal tag, serde would buffer the message and the
+// raw event data can not be read from the buffer
+impl<'de> Deserialize<'de> for Response {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let message = Box::<RawValue>::deserialize(deserializer)?;
+        Response::from_raw(&message).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Response {
+    /// Splits a text frame into its messages, without parsing them
+    ///
+    /// With coalesced messages a frame holds an array of messages.
+    pub(crate) fn split_frame(data: &str) -> HassResult<Vec<&RawValue>> {
+        let messages = match data.trim_start().starts_with('[') {
+            true => serde_json::from_str(data),
+            false => serde_json::from_str(data).map(|message| vec![message]),
+        };
+        messages.map_err(|_| HassError::UnknownPayloadReceived)
+    }
+
+    pub(crate) fn parse(message: &RawValue) -> HassResult<Response> {
+        Response::from_raw(message).map_err(|_| HassError::UnknownPayloadReceived)
+    }
+
+    // decodes the message as the type named in the envelope
+    fn from_raw(message: &RawValue) -> Result<Response, serde_json::Error> {
+        let json = message.get();
+        let envelope: Envelope = serde_json::from_str(json)?;
+        let response = match envelope.msg_type.as_ref() {
+            "auth_required" => Response::AuthRequired(serde_json::from_str(json)?),
+            "auth_ok" => Response::AuthOk(serde_json::from_str(json)?),
+            "auth_invalid" => Response::AuthInvalid(serde_json::from_str(json)?),
+            "event" => Response::Event(serde_json::from_str(json)?),
+            "result" => Response::Result(serde_json::from_str(json)?),
+            "pong" => Response::Pong(serde_json::from_str(json)?),
+            _ => Response::Unknown,
+        };
+        Ok(response)
+    }
+}
+
+/// The fields used to route a message, read without decoding the rest of it
+#[derive(Debug, Deserialize)]
+pub(crate) struct Envelope<'a> {
+    #[serde(default)]
+    pub(crate) id: Option<u64>,
+    #[serde(rename = "type", borrow)]
+    pub(crate) msg_type: Cow<'a, str>,
+}
+
+impl<'a> Envelope<'a> {
+    pub(crate) fn read(message: &'a RawValue) -> HassResult<Envelope<'a>> {
+        serde_json::from_str(message.get()).map_err(|_| HassError::UnknownPayloadReceived)
+    }
+
+    pub(crate) fn is_event(&self) -> bool {
+        self.msg_type == "event"
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub(crate) struct AuthRequired {
@@ -41,19 +105,114 @@ pub(crate) struct AuthInvalid {
 pub struct WsResult {
     pub(crate) id: u64,
     pub(crate) success: bool,
-    // pub(crate) result: Value,
-    pub(crate) error: Option<ErrorCode>,
+    pub(crate) result: Option<Value>,
+    pub(crate) error: Option<Box<HaError>>,
 }
 
-#[derive(Debug, Deserialize)]
+impl WsResult {
+    /// The error returned by Home Assistant if the command failed
+    pub fn error(&self) -> Option<&HaError> {
+        self.error.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct WsEvent {
     pub id: u64,
     pub event: HaEvent,
 }
-#[derive(Debug, Deserialize, PartialEq)]
-pub(crate) struct ErrorCode {
-    pub(crate) code: String,
-    pub(crate) message: String,
+/// The error Home Assistant returns when a command fails
+///
+/// Errors raised by services can carry a translation key and placeholders that
+/// are used by the frontend to show a translated message.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct HaError {
+    pub code: HaErrorCode,
+    #[serde(default)]
+    pub message: String,
+    pub translation_domain: Option<String>,
+    pub translation_key: Option<String>,
+    pub translation_placeholders: Option<HashMap<String, String>>,
+}
+
+impl fmt::Display for HaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+/// The error codes of the websocket API
+///
+/// [Error handling](https://developers.home-assistant.io/docs/api/websocket/#error-handling)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HaErrorCode {
+    IdReuse,
+    InvalidFormat,
+    NotFound,
+    NotSupported,
+    HomeAssistantError,
+    ServiceValidationError,
+    UnknownCommand,
+    UnknownError,
+    Unauthorized,
+    Timeout,
+    NotAllowed,
+    TemplateError,
+    /// Codes that are not known by this client, like integration specific ones
+    Other(String),
+}
+
+impl HaErrorCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            HaErrorCode::IdReuse => "id_reuse",
+            HaErrorCode::InvalidFormat => "invalid_format",
+            HaErrorCode::NotFound => "not_found",
+            HaErrorCode::NotSupported => "not_supported",
+            HaErrorCode::HomeAssistantError => "home_assistant_error",
+            HaErrorCode::ServiceValidationError => "service_validation_error",
+            HaErrorCode::UnknownCommand => "unknown_command",
+            HaErrorCode::UnknownError => "unknown_error",
+            HaErrorCode::Unauthorized => "unauthorized",
+            HaErrorCode::Timeout => "timeout",
+            HaErrorCode::NotAllowed => "not_allowed",
+            HaErrorCode::TemplateError => "template_error",
+            HaErrorCode::Other(code) => code,
+        }
+    }
+}
+
+impl From<&str> for HaErrorCode {
+    fn from(code: &str) -> Self {
+        match code {
+            "id_reuse" => HaErrorCode::IdReuse,
+            "invalid_format" => HaErrorCode::InvalidFormat,
+            "not_found" => HaErrorCode::NotFound,
+            "not_supported" => HaErrorCode::NotSupported,
+            "home_assistant_error" => HaErrorCode::HomeAssistantError,
+            "service_validation_error" => HaErrorCode::ServiceValidationError,
+            "unknown_command" => HaErrorCode::UnknownCommand,
+            "unknown_error" => HaErrorCode::UnknownError,
+            "unauthorized" => HaErrorCode::Unauthorized,
+            "timeout" => HaErrorCode::Timeout,
+            "not_allowed" => HaErrorCode::NotAllowed,
+            "template_error" => HaErrorCode::TemplateError,
+            other => HaErrorCode::Other(other.to_owned()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for HaErrorCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Ok(HaErrorCode::from(code.as_str()))
+    }
+}
+
+impl fmt::Display for HaErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
 }
 
 // this is received as a response to a ping request
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The services of Home Assistant by domain and service name
+///
+/// [Fetch Services](https://developers.home-assistant.io/docs/api/websocket/#fetching-services)
+pub type HaServices = BTreeMap<String, BTreeMap<String, ServiceDescription>>;
+
+/// Describes a service and the fields it accepts
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ServiceDescription {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub fields: BTreeMap<String, ServiceField>,
+    /// Present if the service accepts a target, describes what can be targeted
+    pub target: Option<Value>,
+}
+
+impl ServiceDescription {
+    /// All fields of the service, with the fields of collapsed sections flattened
+    pub fn all_fields(&self) -> BTreeMap<&str, &ServiceField> {
+        fn flatten<'a>(
+            fields: &'a BTreeMap<String, ServiceField>,
+            all: &mut BTreeMap<&'a str, &'a ServiceField>,
+        ) {
+            for (name, field) in fields {
+                match &field.fields {
+                    Some(section) => flatten(section, all),
+                    None => {
+                        all.insert(name, field);
+                    }
+                }
+            }
+        }
+        let mut all = BTreeMap::new();
+        flatten(&self.fields, &mut all);
+        all
+    }
+}
+
+/// A field of a service, sections of advanced fields have their own `fields`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ServiceField {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub required: bool,
+    pub example: Option<Value>,
+    pub selector: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<BTreeMap<String, ServiceField>>,
+}
@@ -0,0 +1,114 @@
+use crate::{ColorMode, HaState, HassError, HvacMode, OnOff};
+
+fn state(json: &str) -> HaState {
+    serde_json::from_str(json).expect("valid state")
+}
+
+#[test]
+fn light_state_should_parse() {
+    let light = state(
+        r#"
+    {
+      "entity_id": "light.kitchen",
+      "state": "on",
+      "attributes": {
+        "friendly_name": "Kitchen",
+        "brightness": 180,
+        "color_mode": "color_temp",
+        "supported_color_modes": ["color_temp", "hs"],
+        "color_temp_kelvin": 2700,
+        "rgb_color": [255, 167, 87]
+      }
+    }"#,
+    )
+    .as_light()
+    .unwrap();
+
+    assert!(light.state.is_on());
+    assert_eq!(light.attributes.brightness, Some(180));
+    assert_eq!(light.attributes.color_mode, Some(ColorMode::ColorTemp));
+    assert_eq!(light.attributes.color_temp_kelvin, Some(2700));
+    assert_eq!(light.attributes.rgb_color, Some((255, 167, 87)));
+}
+
+#[test]
+fn climate_state_should_parse() {
+    let climate = state(
+        r#"
+    {
+      "entity_id": "climate.living_room",
+      "state": "heat_cool",
+      "attributes": {
+        "hvac_modes": ["off", "heat", "heat_cool"],
+        "current_temperature": 21.5,
+        "target_temp_low": 20,
+        "target_temp_high": 24
+      }
+    }"#,
+    )
+    .as_climate()
+    .unwrap();
+
+    assert_eq!(climate.state, HvacMode::HeatCool);
+    assert_eq!(climate.attributes.current_temperature, Some(21.5));
+    assert_eq!(climate.attributes.target_temp_low, Some(20.0));
+}
+
+#[test]
+fn sensor_state_should_have_numeric_value() {
+    let sensor = state(
+        r#"
+    {
+      "entity_id": "sensor.outside_temperature",
+      "state": "12.3",
+      "attributes": {
+        "unit_of_measurement": "°C",
+        "device_class": "temperature"
+      }
+    }"#,
+    )
+    .as_sensor()
+    .unwrap();
+
+    assert_eq!(sensor.value(), Some(12.3));
+    assert_eq!(sensor.attributes.unit_of_measurement.as_deref(), Some("°C"));
+    assert_eq!(
+        sensor.attributes.device_class.as_deref(),
+        Some("temperature")
+    );
+}
+
+#[test]
+fn unavailable_state_should_parse_without_attributes() {
+    let switch = state(r#"{"entity_id": "switch.heater", "state": "unavailable"}"#)
+        .as_switch()
+        .unwrap();
+
+    assert_eq!(switch.state, OnOff::Unavailable);
+}
+
+#[test]
+fn wrong_domain_should_return_error() {
+    let result = state(r#"{"entity_id": "switch.heater", "state": "on"}"#).as_light();
+
+    match result {
+        Err(HassError::DomainMismatch {
+            expected,
+            entity_id,
+        }) => {
+            assert_eq!(expected, "light");
+            assert_eq!(entity_id, "switch.heater");
+        }
+        x => panic!("We should have a domain mismatch! {:?}", x),
+    }
+}
+
+#[test]
+fn mismatching_attributes_should_return_error() {
+    let result = state(
+        r#"{"entity_id": "light.kitchen", "state": "on", "attributes": {"brightness": "bright"}}"#,
+    )
+    .as_light();
+
+    assert!(matches!(result, Err(HassError::UnableToDeserialize(_))));
+}
@@ -1 +1,2 @@
+mod domains;
 mod responses;
@@ -1,4 +1,4 @@
-use crate::{HaEventData, HassError, Response};
+use crate::{Envelope, HaErrorCode, HaEventData, HassError, Response};
 
 #[test]
 fn state_chage_should_parse() {
@@ -62,10 +62,13 @@ fn state_chage_should_parse() {
                 match event_data {
                     Ok(HaEventData::StateChangedEvent(event)) => {
                         assert_eq!(event.entity_id, "input_boolean.test");
-                        let new_state = event.new_state.unwrap();
-                        let old_state = event.old_state.unwrap();
+                        let new_state = event.new_state.as_ref().unwrap();
+                        let old_state = event.old_state.as_ref().unwrap();
                         assert_eq!(new_state.state, "on");
-                        assert_eq!(new_state.attributes.unwrap()["friendly_name"], "test");
+                        assert_eq!(
+                            new_state.attributes.as_ref().unwrap()["friendly_name"],
+                            "test"
+                        );
                         assert_eq!(old_state.state, "off");
                     }
                     _ => {
@@ -83,3 +86,175 @@ fn state_chage_should_parse() {
         }
     }
 }
+
+#[test]
+fn error_result_should_parse_error_code() {
+    let payload: Response = serde_json::from_str(
+        r#"
+    {
+      "id": 24,
+      "type": "result",
+      "success": false,
+      "error": {
+        "code": "service_validation_error",
+        "message": "Option hot is not valid",
+        "translation_domain": "input_select",
+        "translation_key": "invalid_option",
+        "translation_placeholders": {"option": "hot"}
+      }
+    }"#,
+    )
+    .unwrap();
+
+    match payload {
+        Response::Result(result) => {
+            let err = HassError::ResponseError(result);
+            assert_eq!(err.error_code(), Some(&HaErrorCode::ServiceValidationError));
+            let ha_error = err.ha_error().unwrap();
+            assert_eq!(ha_error.translation_key.as_deref(), Some("invalid_option"));
+            assert_eq!(
+                ha_error.translation_placeholders.as_ref().unwrap()["option"],
+                "hot"
+            );
+        }
+        x => panic!("We should have a result response! {:?}", x),
+    }
+}
+
+#[test]
+fn unknown_error_code_should_be_kept() {
+    let payload: Response = serde_json::from_str(
+        r#"{"id": 2, "type": "result", "success": false, "error": {"code": "zwave_error", "message": "failed"}}"#,
+    )
+    .unwrap();
+
+    match payload {
+        Response::Result(result) => {
+            let err = HassError::ResponseError(result);
+            assert_eq!(
+                err.error_code(),
+                Some(&HaErrorCode::Other("zwave_error".to_owned()))
+            );
+            assert_eq!(
+                err.to_string(),
+                "The error code:zwave_error with the error message: failed"
+            );
+        }
+        x => panic!("We should have a result response! {:?}", x),
+    }
+}
+
+#[test]
+fn known_error_codes_should_parse() {
+    let cases = [
+        ("not_allowed", HaErrorCode::NotAllowed),
+        ("template_error", HaErrorCode::TemplateError),
+    ];
+
+    for (code, expected) in cases {
+        let parsed: HaErrorCode = serde_json::from_value(serde_json::json!(code)).unwrap();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed.as_str(), code);
+    }
+}
+
+#[test]
+fn failed_result_without_error_should_display() {
+    let payload: Response =
+        serde_json::from_str(r#"{"id": 3, "type": "result", "success": false}"#).unwrap();
+
+    match payload {
+        Response::Result(result) => {
+            let err = HassError::ResponseError(result);
+            assert_eq!(err.error_code(), None);
+            assert_eq!(
+                err.to_string(),
+                "The command 3 failed without an error message"
+            );
+        }
+        x => panic!("We should have a result response! {:?}", x),
+    }
+}
+
+#[test]
+fn coalesced_frame_should_split_into_messages() {
+    let messages = Response::split_frame(
+        r#"[
+      {"id": 3, "type": "result", "success": true, "result": null},
+      {"id": 4, "type": "pong"},
+      {"id": 2, "type": "event", "event": {
+        "event_type": "call_service",
+        "data": {},
+        "origin": "LOCAL",
+        "time_fired": "2023-08-28T09:08:13.985677+00:00"
+      }}
+    ]"#,
+    )
+    .unwrap();
+
+    assert_eq!(messages.len(), 3);
+    assert!(matches!(
+        Response::parse(messages[0]),
+        Ok(Response::Result(_))
+    ));
+    assert!(matches!(
+        Response::parse(messages[1]),
+        Ok(Response::Pong(_))
+    ));
+    let envelope = Envelope::read(messages[2]).unwrap();
+    assert!(envelope.is_event());
+    assert_eq!(envelope.id, Some(2));
+}
+
+#[test]
+fn single_frame_should_split_into_one_message() {
+    let messages = Response::split_frame(r#"{"id": 4, "type": "pong"}"#).unwrap();
+
+    assert_eq!(messages.len(), 1);
+    assert!(matches!(
+        Response::parse(messages[0]),
+        Ok(Response::Pong(_))
+    ));
+}
+
+#[test]
+fn event_data_should_be_decoded_once_for_all_clones() {
+    let payload = r#"{"id": 1, "type": "event", "event": {
+        "event_type": "state_changed",
+        "data": {"entity_id": "light.kitchen", "new_state": null, "old_state": null},
+        "origin": "LOCAL",
+        "time_fired": "2023-08-28T09:08:13.985677+00:00"
+    }}"#;
+    let event = match serde_json::from_str::<Response>(payload) {
+        Ok(Response::Event(event)) => event.event,
+        x => panic!("We should have an event response! {:?}", x),
+    };
+    let clone = event.clone();
+
+    let first = event.event_data().unwrap();
+    let second = clone.event_data().unwrap();
+    assert!(std::ptr::eq(first, second));
+    assert!(event
+        .raw_data()
+        .get()
+        .starts_with(r#"{"entity_id": "light.kitchen""#));
+}
+
+#[test]
+fn invalid_event_data_should_keep_error_position() {
+    let payload = r#"{"id": 1, "type": "event", "event": {
+        "event_type": "state_changed",
+        "data": {"entity_id": 42},
+        "origin": "LOCAL",
+        "time_fired": "2023-08-28T09:08:13.985677+00:00"
+    }}"#;
+    let event = match serde_json::from_str::<Response>(payload) {
+        Ok(Response::Event(event)) => event.event,
+        x => panic!("We should have an event response! {:?}", x),
+    };
+
+    let borrowed = event.event_data().unwrap_err();
+    let owned = event.get_event_data().unwrap_err();
+    assert_eq!(borrowed.column(), 16);
+    assert_eq!(owned.to_string(), borrowed.to_string());
+}
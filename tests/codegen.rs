@@ -0,0 +1,75 @@
+//! Compiles the code generated from `tests/codegen/snapshot.json`
+//!
+//! Regenerate the fixture after changing the generator with
+//! `cargo run --bin hass-codegen -- --snapshot tests/codegen/snapshot.json --out tests/codegen/generated.rs`
+use r_hassclient::{
+    codegen::{generate, Snapshot},
+    services::{input_select, light, notify, ServiceCall},
+};
+use serde_json::json;
+use std::{path::Path, time::Duration};
+
+mod generated {
+    include!("codegen/generated.rs");
+}
+
+use generated::{entities, services};
+
+#[test]
+fn fixture_should_match_generator() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/codegen/snapshot.json");
+    let snapshot = Snapshot::load(path).unwrap();
+
+    assert_eq!(
+        generate(&snapshot),
+        include_str!("codegen/generated.rs"),
+        "the generated fixture is outdated, regenerate it with hass-codegen"
+    );
+}
+
+#[test]
+fn generated_entities_should_have_ids() {
+    assert_eq!(entities::light::KITCHEN.as_str(), "light.kitchen");
+    assert_eq!(entities::light::ALL.len(), 2);
+    assert_eq!(entities::sensor::_1ST_FLOOR_TEMPERATURE.domain(), "sensor");
+}
+
+#[test]
+fn generated_services_should_match_typed_builders() {
+    let generated: ServiceCall = services::light::turn_on(entities::light::KITCHEN)
+        .transition(2.0)
+        .rgb_color([255, 128, 0])
+        .flash("short")
+        .into();
+    let typed: ServiceCall = light::turn_on("light.kitchen")
+        .transition(Duration::from_secs(2))
+        .rgb_color(255, 128, 0)
+        .flash(light::Flash::Short)
+        .into();
+    assert_eq!(generated, typed);
+
+    let generated: ServiceCall = services::light::turn_off(entities::light::LIVING_ROOM).into();
+    let typed: ServiceCall = light::turn_off("light.living_room").into();
+    assert_eq!(generated, typed);
+
+    let generated: ServiceCall =
+        services::input_select::select_option(entities::input_select::HOUSE_MODE, "away").into();
+    assert_eq!(
+        generated,
+        input_select::select_option("input_select.house_mode", "away")
+    );
+}
+
+#[test]
+fn generated_services_without_target_should_send_data() {
+    let generated: ServiceCall = services::notify::mobile_app_phone("Door is open")
+        .title("Alarm")
+        .data(json!({"push": {"sound": "alarm.caf"}}))
+        .into();
+    let typed: ServiceCall = notify::send("mobile_app_phone", "Door is open")
+        .title("Alarm")
+        .data(json!({"push": {"sound": "alarm.caf"}}))
+        .into();
+
+    assert_eq!(generated, typed);
+}
@@ -0,0 +1,125 @@
+// This file is generated by hass-codegen from a Home Assistant instance, do not edit.
+
+#[allow(dead_code)]
+pub mod entities {
+    pub mod input_select {
+        use r_hassclient::codegen::EntityId;
+
+        /// House mode
+        pub const HOUSE_MODE: EntityId = EntityId::new("input_select.house_mode");
+
+        pub const ALL: &[EntityId] = &[HOUSE_MODE];
+    }
+
+    pub mod light {
+        use r_hassclient::codegen::EntityId;
+
+        /// Kitchen light
+        pub const KITCHEN: EntityId = EntityId::new("light.kitchen");
+
+        pub const LIVING_ROOM: EntityId = EntityId::new("light.living_room");
+
+        pub const ALL: &[EntityId] = &[KITCHEN, LIVING_ROOM];
+    }
+
+    pub mod sensor {
+        use r_hassclient::codegen::EntityId;
+
+        pub const _1ST_FLOOR_TEMPERATURE: EntityId = EntityId::new("sensor.1st_floor_temperature");
+
+        pub const ALL: &[EntityId] = &[_1ST_FLOOR_TEMPERATURE];
+    }
+}
+
+#[allow(dead_code, unused_imports, non_camel_case_types, clippy::all)]
+pub mod services {
+    pub mod input_select {
+        use r_hassclient::services::{ServiceCall, Target};
+
+        /// Select
+        pub fn select_option(target: impl Into<Target>, option: &str) -> SelectOption {
+            SelectOption(ServiceCall::new("input_select", "select_option").target(target).data("option", option))
+        }
+
+        pub struct SelectOption(ServiceCall);
+
+        impl From<SelectOption> for ServiceCall {
+            fn from(service_call: SelectOption) -> Self {
+                service_call.0
+            }
+        }
+    }
+
+    pub mod light {
+        use r_hassclient::services::{ServiceCall, Target};
+
+        /// Turn off
+        pub fn turn_off(target: impl Into<Target>) -> TurnOff {
+            TurnOff(ServiceCall::new("light", "turn_off").target(target))
+        }
+
+        pub struct TurnOff(ServiceCall);
+
+        impl From<TurnOff> for ServiceCall {
+            fn from(service_call: TurnOff) -> Self {
+                service_call.0
+            }
+        }
+
+        /// Turn on
+        ///
+        /// Turns on one or more lights.
+        pub fn turn_on(target: impl Into<Target>) -> TurnOn {
+            TurnOn(ServiceCall::new("light", "turn_on").target(target))
+        }
+
+        pub struct TurnOn(ServiceCall);
+
+        impl TurnOn {
+            pub fn flash(self, value: &str) -> TurnOn {
+                TurnOn(self.0.data("flash", value))
+            }
+
+            /// The color in RGB format.
+            pub fn rgb_color(self, value: [u8; 3]) -> TurnOn {
+                TurnOn(self.0.data("rgb_color", value.to_vec()))
+            }
+
+            pub fn transition(self, value: f64) -> TurnOn {
+                TurnOn(self.0.data("transition", value))
+            }
+        }
+
+        impl From<TurnOn> for ServiceCall {
+            fn from(service_call: TurnOn) -> Self {
+                service_call.0
+            }
+        }
+    }
+
+    pub mod notify {
+        use r_hassclient::services::{ServiceCall, Target};
+
+        pub fn mobile_app_phone(message: &str) -> MobileAppPhone {
+            MobileAppPhone(ServiceCall::new("notify", "mobile_app_phone").data("message", message))
+        }
+
+        pub struct MobileAppPhone(ServiceCall);
+
+        impl MobileAppPhone {
+            pub fn data(self, value: impl Into<r_hassclient::codegen::Value>) -> MobileAppPhone {
+                MobileAppPhone(self.0.data("data", value))
+            }
+
+            pub fn title(self, value: &str) -> MobileAppPhone {
+                MobileAppPhone(self.0.data("title", value))
+            }
+        }
+
+        impl From<MobileAppPhone> for ServiceCall {
+            fn from(service_call: MobileAppPhone) -> Self {
+                service_call.0
+            }
+        }
+    }
+}
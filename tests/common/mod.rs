@@ -0,0 +1,87 @@
+//! Helpers shared by the integration tests
+// Every test binary only uses some of the helpers
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// A request received by the HTTP server
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    /// The path with the query
+    pub path: String,
+    pub authorization: String,
+    pub body: String,
+}
+
+pub type Requests = Arc<Mutex<Vec<Request>>>;
+
+/// Serves HTTP on a local port, the responses are picked by the request
+///
+/// Strings are sent as text and other values as json. Every received
+/// request is kept.
+pub async fn start_server<F>(respond: F) -> (url::Url, Requests)
+where
+    F: Fn(&Request) -> (u16, Value) + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = url::Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+    let requests = Requests::default();
+    let received = Arc::clone(&requests);
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let request = read_request(&mut socket).await;
+            let (status, response) = respond(&request);
+            received.lock().unwrap().push(request);
+            let (content_type, body) = match response {
+                Value::String(text) => ("text/plain", text),
+                value => ("application/json", value.to_string()),
+            };
+            let reply = format!(
+                "HTTP/1.1 {} OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            );
+            socket.write_all(reply.as_bytes()).await.unwrap();
+        }
+    });
+    (url, requests)
+}
+
+async fn read_request(socket: &mut TcpStream) -> Request {
+    let mut data = Vec::new();
+    let mut buffer = [0; 1024];
+    loop {
+        let read = socket.read(&mut buffer).await.unwrap();
+        data.extend_from_slice(&buffer[..read]);
+        let request = String::from_utf8_lossy(&data).to_string();
+        if let Some((head, body)) = request.split_once("\r\n\r\n") {
+            let header = |wanted: &str| {
+                head.lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case(wanted)
+                        .then(|| value.trim().to_owned())
+                })
+            };
+            let length = header("content-length")
+                .map(|length| length.parse::<usize>().unwrap())
+                .unwrap_or_default();
+            if body.len() >= length || read == 0 {
+                let mut request_line = head.split(' ');
+                return Request {
+                    method: request_line.next().unwrap_or_default().to_owned(),
+                    path: request_line.next().unwrap_or_default().to_owned(),
+                    authorization: header("authorization").unwrap_or_default(),
+                    body: body.to_owned(),
+                };
+            }
+        }
+    }
+}
@@ -1,16 +1,11 @@
 use ctor::{ctor, dtor};
 use r_hassclient::client::HaConnection;
-use r_hassclient::{HaClient, HaEventData, HassResult, WsEvent};
+use r_hassclient::services::ServiceCall;
+use r_hassclient::{HaClient, HaEventData, HassError, HassResult, WsEvent};
 use serde_json::json;
-use std::borrow::Borrow;
-use std::fmt::format;
-use std::sync::atomic::AtomicBool;
-use std::sync::Arc;
 use std::{collections::HashMap, time::Duration};
 use std::{future::Future, thread};
 use testcontainers::{core::WaitFor, *};
-use tokio::sync::oneshot;
-use tokio::time::timeout;
 use tokio::{
     runtime,
     sync::{
@@ -314,3 +309,126 @@ async fn should_be_able_to_subscribe_to_events() {
        _= rx.recv() => { },
     }
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn should_be_able_to_toggle_entity_with_handle() {
+    let mut conn = match connect_to_home_assistant().await {
+        Err(err) => {
+            panic!("Failed to connect to Home Assistant: {}", err);
+        }
+        Ok(conn) => conn,
+    };
+
+    if let Err(helper_res) = conn.create_helper("input_boolean", "entity_handle").await {
+        panic!("Failed to create input_boolean helper: {}", helper_res);
+    }
+
+    let mut entity = conn.entity("input_boolean.entity_handle");
+    let (listener_id, mut changes) = entity.changes().await.expect("Failed to subscribe");
+
+    entity.turn_on().await.expect("Failed to turn on entity");
+
+    tokio::select! {
+        _ = tokio::time::sleep(Duration::from_millis(2000)) => {
+            panic!("Timeout waiting for entity change");
+        }
+        change = changes.recv() => {
+            assert_eq!(change.unwrap().new_state.unwrap().state, "on");
+        },
+    }
+
+    let state = entity.state().await.expect("Failed to read state");
+    assert_eq!(state.unwrap().state, "on");
+    entity
+        .unsubscribe(listener_id)
+        .await
+        .expect("Failed to unsubscribe");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn should_wait_for_state_after_service_call() {
+    let mut conn = match connect_to_home_assistant().await {
+        Err(err) => {
+            panic!("Failed to connect to Home Assistant: {}", err);
+        }
+        Ok(conn) => conn,
+    };
+
+    if let Err(helper_res) = conn.create_helper("input_boolean", "wait_for_state").await {
+        panic!("Failed to create input_boolean helper: {}", helper_res);
+    }
+
+    let state = conn
+        .call_service_and_wait(
+            ServiceCall::new("input_boolean", "turn_on").target("input_boolean.wait_for_state"),
+            "input_boolean.wait_for_state",
+            |state| state.state == "on",
+            Duration::from_secs(2),
+        )
+        .await
+        .expect("Failed to wait for the service call");
+    assert_eq!(state.state, "on");
+
+    let state = conn
+        .wait_for_state(
+            "input_boolean.wait_for_state",
+            |state| state.state == "on",
+            Duration::from_secs(2),
+        )
+        .await
+        .expect("Failed to wait for state");
+    assert_eq!(state.state, "on");
+
+    let result = conn
+        .wait_for_state(
+            "input_boolean.wait_for_state",
+            |state| state.state == "off",
+            Duration::from_millis(200),
+        )
+        .await;
+    assert!(matches!(result, Err(HassError::Timeout(_))));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn listeners_should_share_subscription() {
+    let mut conn = match connect_to_home_assistant().await {
+        Err(err) => {
+            panic!("Failed to connect to Home Assistant: {}", err);
+        }
+        Ok(conn) => conn,
+    };
+
+    if let Err(helper_res) = conn.create_helper("input_boolean", "shared").await {
+        panic!("Failed to create input_boolean helper: {}", helper_res);
+    }
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let first = conn
+        .subscribe_async("state_changed", |_event: WsEvent| async {})
+        .await
+        .expect("Failed to subscribe");
+    conn.subscribe_async("state_changed", move |event: WsEvent| {
+        let tx = tx.clone();
+        async move {
+            _ = tx.send(event);
+        }
+    })
+    .await
+    .expect("Failed to subscribe");
+
+    // The second listener still receives events after the first is removed
+    conn.unsubscribe(first)
+        .await
+        .expect("Failed to unsubscribe");
+    conn.entity("input_boolean.shared")
+        .turn_on()
+        .await
+        .expect("Failed to turn on entity");
+
+    tokio::select! {
+        _ = tokio::time::sleep(Duration::from_millis(2000)) => {
+            panic!("Timeout waiting for state_changed event");
+        }
+        event = rx.recv() => assert_eq!(event.unwrap().event.event_type, "state_changed"),
+    }
+}
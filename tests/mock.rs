@@ -0,0 +1,312 @@
+use futures_util::{future::BoxFuture, StreamExt};
+use r_hassclient::{
+    auth::{Secret, TokenSource},
+    client::HaConnection,
+    mock::{Fault, MockServer},
+    services::ServiceCall,
+    HaClient, HassError, HassResult, Query, SlowConsumerPolicy, StateStore,
+};
+use serde_json::json;
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+use tokio::time::{timeout, Instant};
+
+async fn connect(server: &MockServer) -> HaConnection {
+    let mut conn = HaClient::builder()
+        .build()
+        .connect_with(server.connect())
+        .await
+        .expect("Failed to connect to the mock server");
+    conn.authenticate_with_token("token")
+        .await
+        .expect("Failed to authenticate");
+    conn
+}
+
+#[tokio::test]
+async fn service_call_should_change_state_and_fire_event() {
+    let server = MockServer::start().await.unwrap();
+    server.set_state("light.kitchen", "off", json!({"friendly_name": "Kitchen"}));
+    let mut conn = HaClient::builder()
+        .build()
+        .connect_async(server.url())
+        .await
+        .unwrap();
+    conn.authenticate_with_token("token").await.unwrap();
+
+    let state = conn
+        .call_service_and_wait(
+            ServiceCall::new("light", "turn_on").target("light.kitchen"),
+            "light.kitchen",
+            |state| state.state == "on",
+            Duration::from_secs(2),
+        )
+        .await
+        .expect("Failed to wait for the service call");
+
+    assert_eq!(state.state, "on");
+    assert!(state.context.is_some());
+    let calls = server.service_calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].service, "turn_on");
+    assert_eq!(calls[0].entity_ids, vec!["light.kitchen"]);
+}
+
+#[tokio::test]
+async fn invalid_token_should_fail_authentication() {
+    let server = MockServer::new();
+    server.set_token("secret");
+    let mut conn = HaClient::builder()
+        .build()
+        .connect_with(server.connect())
+        .await
+        .unwrap();
+
+    let result = conn.authenticate_with_token("wrong").await;
+
+    assert!(matches!(result, Err(HassError::AuthenticationFailed(_))));
+}
+
+/// Hands out the token of the current generation, rotating it when rejected
+#[derive(Default)]
+struct RotatingToken(AtomicU32);
+
+impl TokenSource for RotatingToken {
+    fn token(&self) -> BoxFuture<'_, HassResult<Secret>> {
+        let generation = self.0.load(Ordering::SeqCst);
+        Box::pin(async move { Ok(Secret::new(format!("token-{}", generation))) })
+    }
+
+    fn rejected(&self) -> BoxFuture<'_, ()> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async {})
+    }
+}
+
+#[tokio::test]
+async fn token_source_should_be_told_about_rejected_tokens() {
+    let server = MockServer::new();
+    server.set_token("token-1");
+    let mut client = HaClient::builder()
+        .token_source(RotatingToken::default())
+        .build();
+
+    let mut conn = client.connect_with(server.connect()).await.unwrap();
+    assert!(conn.authenticate().await.is_err());
+    let mut conn = client.connect_with(server.connect()).await.unwrap();
+    conn.authenticate().await.unwrap();
+}
+
+#[tokio::test]
+async fn store_should_follow_scripted_states() {
+    let server = MockServer::new();
+    server.set_state(
+        "sensor.temperature",
+        "21.5",
+        json!({"device_class": "temperature"}),
+    );
+    let mut conn = connect(&server).await;
+
+    let store = StateStore::new();
+    store.attach(&mut conn).await.unwrap();
+    assert_eq!(store.get("sensor.temperature").unwrap().state, "21.5");
+
+    let mut changes = Box::pin(store.watch(Query::new().domain("sensor")));
+    server.set_state(
+        "sensor.temperature",
+        "22.0",
+        json!({"device_class": "temperature"}),
+    );
+
+    let change = timeout(Duration::from_secs(1), changes.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(change.new_state.as_ref().unwrap().state, "22.0");
+    assert_eq!(store.get("sensor.temperature").unwrap().state, "22.0");
+}
+
+#[tokio::test]
+async fn store_attached_twice_should_apply_changes_once() {
+    let server = MockServer::new();
+    server.set_state("sensor.temperature", "21.5", json!({}));
+    let mut conn = connect(&server).await;
+
+    let store = StateStore::new();
+    let first = store.attach(&mut conn).await.unwrap();
+    let second = store.attach(&mut conn).await.unwrap();
+    assert_ne!(first, second);
+
+    let mut changes = Box::pin(store.watch(Query::new()));
+    server.set_state("sensor.temperature", "22.0", json!({}));
+    let change = timeout(Duration::from_secs(1), changes.next()).await;
+    assert_eq!(
+        change.unwrap().unwrap().new_state.as_ref().unwrap().state,
+        "22.0"
+    );
+    let duplicate = timeout(Duration::from_millis(100), changes.next()).await;
+    assert!(duplicate.is_err());
+
+    store.detach(&mut conn).await.unwrap();
+    server.set_state("sensor.temperature", "23.0", json!({}));
+    let detached = timeout(Duration::from_millis(100), changes.next()).await;
+    assert!(detached.is_err());
+    assert_eq!(store.get("sensor.temperature").unwrap().state, "22.0");
+}
+
+#[tokio::test]
+async fn callback_should_not_need_to_be_sync() {
+    let server = MockServer::new();
+    let mut conn = connect(&server).await;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    // A Cell is Send but not Sync
+    let count = Cell::new(0);
+
+    conn.subscribe_message("my_event", move |_| {
+        count.set(count.get() + 1);
+        _ = tx.send(count.get());
+    })
+    .await
+    .unwrap();
+    server.fire_event("my_event", json!({}));
+    server.fire_event("my_event", json!({}));
+
+    let mut counts = Vec::new();
+    for _ in 0..2 {
+        counts.push(timeout(Duration::from_secs(1), rx.recv()).await.unwrap());
+    }
+    assert_eq!(counts, vec![Some(1), Some(2)]);
+}
+
+#[tokio::test]
+async fn disconnected_slow_listener_should_cancel_subscription() {
+    let server = MockServer::new();
+    let mut conn = HaClient::builder()
+        .listener_capacity(1)
+        .slow_consumer_policy(SlowConsumerPolicy::Disconnect)
+        .build()
+        .connect_with(server.connect())
+        .await
+        .unwrap();
+    conn.authenticate_with_token("token").await.unwrap();
+    conn.subscribe_async("my_event", |_| std::future::pending::<()>())
+        .await
+        .unwrap();
+    assert_eq!(server.subscription_count(), 1);
+
+    // The callback never returns, the third event overflows its queue
+    for _ in 0..3 {
+        server.fire_event("my_event", json!({}));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // The subscription is cancelled before the next command
+    conn.ping().await.unwrap();
+    assert_eq!(server.subscription_count(), 0);
+}
+
+#[tokio::test]
+async fn helper_should_be_created() {
+    let server = MockServer::new();
+    let mut conn = connect(&server).await;
+
+    conn.create_helper("input_boolean", "test").await.unwrap();
+    conn.entity("input_boolean.test").toggle().await.unwrap();
+
+    assert_eq!(server.state("input_boolean.test").unwrap().state, "on");
+}
+
+#[tokio::test]
+async fn entity_should_read_state_and_follow_changes() {
+    let server = MockServer::new();
+    server.set_state("light.kitchen", "off", json!({"friendly_name": "Kitchen"}));
+    server.set_state("light.hall", "off", json!({}));
+    let mut conn = connect(&server).await;
+    assert!(conn
+        .entity("light.unknown")
+        .state()
+        .await
+        .unwrap()
+        .is_none());
+    let mut kitchen = conn.entity("light.kitchen");
+
+    assert_eq!(kitchen.state().await.unwrap().unwrap().state, "off");
+    let (listener_id, mut changes) = kitchen.changes().await.unwrap();
+    server.set_state("light.hall", "on", json!({}));
+    server.set_state("light.kitchen", "on", json!({}));
+
+    let change = timeout(Duration::from_secs(1), changes.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(change.entity_id, "light.kitchen");
+    assert_eq!(change.new_state.unwrap().state, "on");
+
+    kitchen.unsubscribe(listener_id).await.unwrap();
+    server.set_state("light.kitchen", "off", json!({}));
+    let closed = timeout(Duration::from_secs(1), changes.recv())
+        .await
+        .unwrap();
+    assert!(closed.is_none());
+}
+
+#[tokio::test]
+async fn entity_service_call_should_target_entity() {
+    let server = MockServer::new();
+    server.set_state("light.kitchen", "off", json!({}));
+    let mut conn = connect(&server).await;
+    let mut kitchen = conn.entity("light.kitchen");
+
+    kitchen
+        .call_service("turn_on", Some(json!({"brightness": 120})))
+        .await
+        .unwrap();
+    let invalid = kitchen.call_service("turn_on", Some(json!([120]))).await;
+
+    assert!(matches!(invalid, Err(HassError::GenericError(_))));
+    let calls = server.service_calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].service, "turn_on");
+    assert_eq!(calls[0].entity_ids, vec!["light.kitchen"]);
+    assert_eq!(calls[0].service_data["brightness"], 120);
+    assert_eq!(server.state("light.kitchen").unwrap().state, "on");
+}
+
+#[tokio::test]
+async fn malformed_frame_should_fail_command() {
+    let server = MockServer::new();
+    let mut conn = connect(&server).await;
+
+    server.inject_fault(Fault::Malformed);
+
+    assert!(matches!(
+        conn.ping().await,
+        Err(HassError::UnknownPayloadReceived)
+    ));
+    assert_eq!(conn.ping().await.unwrap(), "pong");
+}
+
+#[tokio::test]
+async fn delayed_reply_should_arrive_late() {
+    let server = MockServer::new();
+    let mut conn = connect(&server).await;
+
+    server.inject_fault(Fault::Delay(Duration::from_millis(100)));
+    let start = Instant::now();
+
+    assert_eq!(conn.ping().await.unwrap(), "pong");
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn dropped_connection_should_fail_command() {
+    let server = MockServer::new();
+    let mut conn = connect(&server).await;
+
+    server.inject_fault(Fault::Drop);
+
+    assert!(matches!(conn.ping().await, Err(HassError::ConnectionError)));
+}
@@ -0,0 +1,134 @@
+use r_hassclient::{auth::TokenProvider, mock::MockServer, HaClient, HassError};
+use serde_json::{json, Value};
+
+mod common;
+use common::start_server;
+
+fn tokens(access_token: &str, expires_in: u64) -> Value {
+    json!({
+        "access_token": access_token,
+        "expires_in": expires_in,
+        "refresh_token": "refresh-1",
+        "token_type": "Bearer"
+    })
+}
+
+#[tokio::test]
+async fn login_should_exchange_code_for_tokens() {
+    let (url, requests) = start_server(|request| match request.path.as_str() {
+        "/auth/login_flow" => (200, json!({"type": "form", "flow_id": "flow-1"})),
+        "/auth/login_flow/flow-1" if request.body.contains("P@ssword") => {
+            (200, json!({"type": "create_entry", "result": "code-1"}))
+        }
+        "/auth/token" if request.body.contains("code=code-1") => (200, tokens("access-1", 1800)),
+        _ => (400, json!({"error": "invalid_request"})),
+    })
+    .await;
+
+    let provider = TokenProvider::new(url, "http://client");
+    provider.login("user", "P@ssword").await.unwrap();
+
+    assert_eq!(provider.access_token().await.unwrap().expose(), "access-1");
+    assert_eq!(
+        provider.refresh_token().await.unwrap().expose(),
+        "refresh-1"
+    );
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[2].body.contains("grant_type=authorization_code"));
+}
+
+#[tokio::test]
+async fn rejected_login_should_fail() {
+    let (url, _) = start_server(|request| match request.path.as_str() {
+        "/auth/login_flow" => (200, json!({"type": "form", "flow_id": "flow-1"})),
+        _ => (
+            200,
+            json!({"type": "form", "flow_id": "flow-1", "errors": {"base": "invalid_auth"}}),
+        ),
+    })
+    .await;
+
+    let provider = TokenProvider::new(url, "http://client");
+    let result = provider.login("user", "wrong").await;
+
+    assert!(
+        matches!(result, Err(HassError::AuthenticationFailed(message)) if message.contains("invalid_auth"))
+    );
+}
+
+#[tokio::test]
+async fn login_should_keep_path_of_base_url() {
+    let (url, requests) = start_server(|request| match request.path.as_str() {
+        "/ha/auth/login_flow" => (200, json!({"type": "form", "flow_id": "flow-1"})),
+        "/ha/auth/login_flow/flow-1" => (200, json!({"type": "create_entry", "result": "code-1"})),
+        "/ha/auth/token" => (200, tokens("access-1", 1800)),
+        _ => (404, json!({"error": "not_found"})),
+    })
+    .await;
+
+    let provider = TokenProvider::new(url.join("ha").unwrap(), "http://client");
+    provider.login("user", "P@ssword").await.unwrap();
+
+    assert_eq!(provider.access_token().await.unwrap().expose(), "access-1");
+    assert_eq!(requests.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn expiring_access_token_should_be_refreshed() {
+    let (url, requests) = start_server(|request| {
+        if request.body.contains("grant_type=refresh_token") {
+            (200, tokens("access-2", 1800))
+        } else {
+            (200, tokens("access-1", 30))
+        }
+    })
+    .await;
+
+    let provider = TokenProvider::new(url, "http://client");
+    provider.exchange_code("code-1").await.unwrap();
+
+    assert_eq!(provider.access_token().await.unwrap().expose(), "access-2");
+    assert_eq!(provider.access_token().await.unwrap().expose(), "access-2");
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn rejected_access_token_should_be_refreshed_on_next_authentication() {
+    let (url, requests) = start_server(|request| {
+        if request.body.contains("refresh_token=stored") {
+            // Home Assistant keeps the refresh token when refreshing
+            (200, json!({"access_token": "access-2", "expires_in": 1800}))
+        } else {
+            (400, json!({"error": "invalid_grant"}))
+        }
+    })
+    .await;
+    let server = MockServer::start().await.unwrap();
+    server.set_token("access-2");
+
+    let provider = TokenProvider::new(url, "http://client").with_refresh_token("stored");
+    let mut conn = HaClient::builder()
+        .build()
+        .connect_with(server.connect())
+        .await
+        .unwrap();
+    conn.authenticate_with(&provider).await.unwrap();
+
+    // Home Assistant revoked the token, the provider refreshes it for the next connection
+    server.set_token("access-3");
+    let mut conn = HaClient::builder()
+        .build()
+        .connect_with(server.connect())
+        .await
+        .unwrap();
+    assert!(conn.authenticate_with(&provider).await.is_err());
+    server.set_token("access-2");
+    let mut conn = HaClient::builder()
+        .build()
+        .connect_with(server.connect())
+        .await
+        .unwrap();
+    conn.authenticate_with(&provider).await.unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
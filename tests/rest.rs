@@ -0,0 +1,403 @@
+use futures_util::future::BoxFuture;
+use r_hassclient::{
+    auth::{Secret, StaticToken, TokenSource},
+    mock::MockServer,
+    rest::{SensorPublisher, VirtualSensor},
+    services::ServiceCall,
+    HaClient, HaRestClient, HassError, HassResult,
+};
+use serde_json::{json, Map, Value};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use tokio::time::timeout;
+
+mod common;
+use common::{start_server, Request, Requests};
+
+fn state(entity_id: &str, state: &str) -> Value {
+    json!({
+        "entity_id": entity_id,
+        "state": state,
+        "attributes": {"friendly_name": "Outside"},
+        "last_changed": "2024-01-01T00:00:00+00:00",
+        "last_updated": "2024-01-01T00:00:00+00:00",
+        "context": {"id": "01HK", "parent_id": null, "user_id": null}
+    })
+}
+
+fn client(url: url::Url) -> HaRestClient {
+    HaRestClient::new(url, StaticToken::new("token-1"))
+}
+
+#[tokio::test]
+async fn states_should_be_read_with_bearer_token() {
+    let (url, requests) = start_server(|request| match request.path.as_str() {
+        "/api/states" => (200, json!([state("sensor.outside", "12.5")])),
+        "/api/states/sensor.outside" => (200, state("sensor.outside", "12.5")),
+        _ => (404, json!({"message": "Entity not found."})),
+    })
+    .await;
+    let rest = client(url);
+
+    let states = rest.get_states().await.unwrap();
+    assert_eq!(states[0].entity_id, "sensor.outside");
+    let outside = rest.get_state("sensor.outside").await.unwrap().unwrap();
+    assert_eq!(outside.state, "12.5");
+    assert!(rest.get_state("sensor.unknown").await.unwrap().is_none());
+
+    let requests = requests.lock().unwrap();
+    assert!(requests
+        .iter()
+        .all(|request| request.authorization == "Bearer token-1"));
+}
+
+#[tokio::test]
+async fn service_call_should_send_target_with_data() {
+    let (url, requests) = start_server(|_| (200, json!([state("light.kitchen", "on")]))).await;
+
+    let changed = client(url)
+        .call_service(
+            ServiceCall::new("light", "turn_on")
+                .target("light.kitchen")
+                .data("brightness", 120),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(changed[0].state, "on");
+    let request = requests.lock().unwrap()[0].clone();
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "/api/services/light/turn_on");
+    let body: Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(
+        body,
+        json!({"entity_id": ["light.kitchen"], "brightness": 120})
+    );
+}
+
+#[tokio::test]
+async fn services_should_be_grouped_by_domain() {
+    let (url, _) = start_server(|_| {
+        (
+            200,
+            json!([{
+                "domain": "light",
+                "services": {"turn_on": {"name": "Turn on", "description": "", "fields": {}}}
+            }]),
+        )
+    })
+    .await;
+
+    let services = client(url).get_services().await.unwrap();
+
+    assert!(services["light"].contains_key("turn_on"));
+}
+
+#[tokio::test]
+async fn history_and_logbook_should_send_filters() {
+    let (url, requests) = start_server(|request| {
+        if request.path.starts_with("/api/history") {
+            (200, json!([[state("sensor.outside", "12.5")]]))
+        } else {
+            (
+                200,
+                json!([{"when": "2024-01-01T00:00:00+00:00", "name": "Outside", "message": "changed to 12.5", "entity_id": "sensor.outside"}]),
+            )
+        }
+    })
+    .await;
+    let rest = client(url);
+
+    let history = rest
+        .get_history(
+            "2024-01-01T00:00:00Z",
+            Some("2024-01-02T00:00:00Z"),
+            &["sensor.outside", "sensor.inside"],
+        )
+        .await
+        .unwrap();
+    let logbook = rest
+        .get_logbook("2024-01-01T00:00:00Z", None, Some("sensor.outside"))
+        .await
+        .unwrap();
+
+    assert_eq!(history[0][0].state, "12.5");
+    assert_eq!(logbook[0].message.as_deref(), Some("changed to 12.5"));
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        requests[0].path,
+        "/api/history/period/2024-01-01T00:00:00Z?filter_entity_id=sensor.outside%2Csensor.inside&end_time=2024-01-02T00%3A00%3A00Z"
+    );
+    assert_eq!(
+        requests[1].path,
+        "/api/logbook/2024-01-01T00:00:00Z?entity=sensor.outside"
+    );
+}
+
+#[tokio::test]
+async fn text_endpoints_should_return_text() {
+    let (url, requests) = start_server(|request| match request.path.as_str() {
+        "/api/template" => (200, json!("above_horizon")),
+        "/api/error_log" => (200, json!("2024-01-01 ERROR something failed")),
+        "/api/camera_proxy/camera.door" => (200, json!("jpeg")),
+        "/api/events/my_event" => (200, json!({"message": "Event my_event fired."})),
+        "/api/config/core/check_config" => (200, json!({"result": "valid", "errors": null})),
+        _ => (404, json!({})),
+    })
+    .await;
+    let rest = client(url);
+
+    let rendered = rest.render_template("{{ states('sun.sun') }}").await;
+    assert_eq!(rendered.unwrap(), "above_horizon");
+    let log = rest.get_error_log().await.unwrap();
+    assert!(log.contains("something failed"));
+    let image = rest.get_camera_image("camera.door").await.unwrap();
+    assert_eq!(image, b"jpeg");
+    let fired = rest.fire_event("my_event", Some(json!({"answer": 42})));
+    assert_eq!(fired.await.unwrap(), "Event my_event fired.");
+    assert_eq!(rest.check_config().await.unwrap().result, "valid");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        requests[0].body,
+        r#"{"template":"{{ states('sun.sun') }}"}"#
+    );
+    assert_eq!(requests[3].body, r#"{"answer":42}"#);
+}
+
+#[tokio::test]
+async fn error_status_should_return_http_error() {
+    let (url, _) = start_server(|_| (500, json!("Internal Server Error"))).await;
+
+    let result = client(url).get_config().await;
+
+    assert!(matches!(result, Err(HassError::HttpError(500, body)) if body.contains("Internal")));
+}
+
+/// Hands out `token-1` until it is rejected, then `token-2`
+#[derive(Default)]
+struct RotatingToken {
+    rejected: AtomicUsize,
+}
+
+impl TokenSource for RotatingToken {
+    fn token(&self) -> BoxFuture<'_, HassResult<Secret>> {
+        let token = format!("token-{}", self.rejected.load(Ordering::SeqCst) + 1);
+        Box::pin(async move { Ok(Secret::new(token)) })
+    }
+
+    fn rejected(&self) -> BoxFuture<'_, ()> {
+        self.rejected.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async {})
+    }
+}
+
+#[tokio::test]
+async fn rejected_token_should_be_replaced_once() {
+    let (url, requests) = start_server(|request| match request.authorization.as_str() {
+        "Bearer token-2" => (200, json!([])),
+        _ => (401, json!("401: Unauthorized")),
+    })
+    .await;
+    let rest = HaRestClient::new(url.clone(), RotatingToken::default());
+
+    assert!(rest.get_states().await.unwrap().is_empty());
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    let rest = client(url);
+    let result = rest.get_states().await;
+    assert!(matches!(result, Err(HassError::AuthenticationFailed(_))));
+}
+
+#[tokio::test]
+async fn client_should_share_url_and_token_source() {
+    let (url, requests) = start_server(|_| (200, json!([]))).await;
+    let mut websocket_url = url.clone();
+    websocket_url.set_scheme("ws").unwrap();
+    websocket_url.set_path("/api/websocket");
+
+    let rest = HaClient::builder()
+        .url(websocket_url)
+        .token_source(StaticToken::new("token-3"))
+        .build()
+        .rest()
+        .unwrap();
+
+    assert_eq!(rest.base_url(), &url);
+    rest.get_states().await.unwrap();
+    assert_eq!(requests.lock().unwrap()[0].authorization, "Bearer token-3");
+}
+
+/// Answers state changes like Home Assistant, with the posted state
+fn echo_state(request: &Request) -> (u16, Value) {
+    let entity_id = request.path.trim_start_matches("/api/states/");
+    match request.method.as_str() {
+        "POST" => {
+            let body: Value = serde_json::from_str(&request.body).unwrap();
+            let mut state = state(entity_id, body["state"].as_str().unwrap());
+            state["attributes"] = body["attributes"].clone();
+            (201, state)
+        }
+        "DELETE" if entity_id == "sensor.dew_point" => (200, json!({"message": "Entity removed."})),
+        _ => (404, json!({"message": "Entity not found."})),
+    }
+}
+
+fn posted(requests: &Requests) -> Vec<Value> {
+    requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|request| request.method == "POST")
+        .map(|request| serde_json::from_str(&request.body).unwrap())
+        .collect()
+}
+
+async fn wait_for_posts(requests: &Requests, count: usize) {
+    timeout(Duration::from_secs(2), async {
+        while posted(requests).len() < count {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the sensors were not republished");
+}
+
+fn dew_point(state: f64) -> VirtualSensor {
+    VirtualSensor::new("sensor.dew_point", state)
+        .friendly_name("Dew point")
+        .unit_of_measurement("°C")
+        .device_class("temperature")
+}
+
+#[tokio::test]
+async fn state_should_be_set_and_deleted() {
+    let (url, requests) = start_server(echo_state).await;
+    let rest = client(url);
+    let mut attributes = Map::new();
+    attributes.insert("friendly_name".to_owned(), json!("Dew point"));
+
+    let state = rest
+        .set_state("sensor.dew_point", "8.2", attributes)
+        .await
+        .unwrap();
+
+    assert_eq!(state.state, "8.2");
+    assert_eq!(state.attributes.unwrap()["friendly_name"], "Dew point");
+    assert!(rest.delete_state("sensor.dew_point").await.unwrap());
+    assert!(!rest.delete_state("sensor.unknown").await.unwrap());
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].path, "/api/states/sensor.dew_point");
+    assert_eq!(requests[1].method, "DELETE");
+}
+
+#[tokio::test]
+async fn publisher_should_remember_last_state() {
+    let (url, requests) = start_server(echo_state).await;
+    let publisher = SensorPublisher::new(client(url));
+
+    publisher.publish(dew_point(8.2)).await.unwrap();
+    publisher
+        .publish(dew_point(8.2).with_state(9.1))
+        .await
+        .unwrap();
+    publisher.republish().await.unwrap();
+
+    let posted = posted(&requests);
+    assert_eq!(posted.len(), 3);
+    assert_eq!(
+        posted[2],
+        json!({
+            "state": "9.1",
+            "attributes": {
+                "friendly_name": "Dew point",
+                "unit_of_measurement": "°C",
+                "device_class": "temperature"
+            }
+        })
+    );
+
+    assert!(publisher.remove("sensor.dew_point").await.unwrap());
+    assert!(publisher.sensors().await.is_empty());
+}
+
+#[tokio::test]
+async fn publisher_should_republish_periodically() {
+    let (url, requests) = start_server(echo_state).await;
+    let publisher = SensorPublisher::new(client(url));
+    publisher.publish(dew_point(8.2)).await.unwrap();
+
+    let task = publisher.republish_every(Duration::from_millis(20));
+
+    wait_for_posts(&requests, 3).await;
+    task.abort();
+}
+
+// The server answers on one task, so blocking it holds the requests queued behind it
+#[tokio::test(flavor = "multi_thread")]
+async fn publish_during_republish_should_keep_new_state() {
+    let (url, requests) = start_server(|request| {
+        if request.path.ends_with("sensor.a_slow") {
+            std::thread::sleep(Duration::from_millis(200));
+        }
+        echo_state(request)
+    })
+    .await;
+    let publisher = SensorPublisher::new(client(url));
+    publisher
+        .publish(VirtualSensor::new("sensor.a_slow", 1))
+        .await
+        .unwrap();
+    publisher.publish(dew_point(8.2)).await.unwrap();
+
+    // The republish is held by the first sensor when the dew point changes
+    let publish = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        publisher.publish(dew_point(9.1)).await
+    };
+    let (republished, published) = tokio::join!(publisher.republish(), publish);
+    republished.unwrap();
+    published.unwrap();
+
+    let last = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|request| request.method == "POST" && request.path.ends_with("sensor.dew_point"))
+        .map(|request| serde_json::from_str::<Value>(&request.body).unwrap())
+        .next_back()
+        .unwrap();
+    assert_eq!(last["state"], "9.1");
+}
+
+#[tokio::test]
+#[should_panic(expected = "must not be zero")]
+async fn zero_republish_period_should_panic() {
+    let (url, _) = start_server(echo_state).await;
+    SensorPublisher::new(client(url)).republish_every(Duration::ZERO);
+}
+
+#[tokio::test]
+async fn publisher_should_republish_on_connection_and_when_home_assistant_started() {
+    let (url, requests) = start_server(echo_state).await;
+    let publisher = SensorPublisher::new(client(url));
+    publisher.publish(dew_point(8.2)).await.unwrap();
+    let server = MockServer::new();
+    let mut conn = HaClient::builder()
+        .build()
+        .connect_with(server.connect())
+        .await
+        .unwrap();
+    conn.authenticate_with_token("token").await.unwrap();
+
+    // Home Assistant may have restarted while the client was disconnected
+    publisher.republish_on_start(&mut conn).await.unwrap();
+    assert_eq!(posted(&requests).len(), 2);
+
+    // The client connected while Home Assistant was starting
+    server.fire_event("homeassistant_started", json!({}));
+    wait_for_posts(&requests, 3).await;
+    assert_eq!(posted(&requests)[2]["state"], "8.2");
+}
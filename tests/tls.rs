@@ -0,0 +1,258 @@
+use futures_util::{SinkExt, StreamExt};
+use r_hassclient::{
+    tls::{TlsBackend, TlsConfig},
+    HaClient, HassResult,
+};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_rustls::{
+    rustls::{server::WebPkiClientVerifier, RootCertStore, ServerConfig},
+    TlsAcceptor,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+struct Identity {
+    certificate_pem: String,
+    private_key_pem: String,
+}
+
+/// A private certificate authority with a server and a client certificate
+struct PrivateCa {
+    ca_pem: String,
+    server: Identity,
+    client: Identity,
+}
+
+fn private_ca() -> PrivateCa {
+    let mut params = CertificateParams::new(vec![]);
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params
+        .distinguished_name
+        .push(DnType::CommonName, "r-hassclient test ca");
+    let ca = Certificate::from_params(params).unwrap();
+
+    let server = Certificate::from_params(CertificateParams::new(vec![
+        "localhost".to_owned(),
+        "::1".to_owned(),
+    ]))
+    .unwrap();
+    let mut params = CertificateParams::new(vec!["client".to_owned()]);
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+    let client = Certificate::from_params(params).unwrap();
+
+    PrivateCa {
+        ca_pem: ca.serialize_pem().unwrap(),
+        server: Identity {
+            certificate_pem: server.serialize_pem_with_signer(&ca).unwrap(),
+            private_key_pem: server.serialize_private_key_pem(),
+        },
+        client: Identity {
+            certificate_pem: client.serialize_pem_with_signer(&ca).unwrap(),
+            private_key_pem: client.serialize_private_key_pem(),
+        },
+    }
+}
+
+fn self_signed() -> Identity {
+    let certificate = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+    Identity {
+        certificate_pem: certificate.serialize_pem().unwrap(),
+        private_key_pem: certificate.serialize_private_key_pem(),
+    }
+}
+
+fn fingerprint(certificate_pem: &str) -> String {
+    let der = rustls_pemfile::certs(&mut certificate_pem.as_bytes())
+        .next()
+        .unwrap()
+        .unwrap();
+    Sha256::digest(der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Answers one ping over `wss`, asking for a client certificate of the ca when given
+async fn start_server(identity: &Identity, client_ca_pem: Option<&str>) -> u16 {
+    start_server_on("127.0.0.1:0", identity, client_ca_pem).await
+}
+
+async fn start_server_on(address: &str, identity: &Identity, client_ca_pem: Option<&str>) -> u16 {
+    let certificates = rustls_pemfile::certs(&mut identity.certificate_pem.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let private_key = rustls_pemfile::private_key(&mut identity.private_key_pem.as_bytes())
+        .unwrap()
+        .unwrap();
+    let builder = ServerConfig::builder();
+    let builder = match client_ca_pem {
+        Some(pem) => {
+            let mut roots = RootCertStore::empty();
+            for certificate in rustls_pemfile::certs(&mut pem.as_bytes()) {
+                roots.add(certificate.unwrap()).unwrap();
+            }
+            builder.with_client_cert_verifier(
+                WebPkiClientVerifier::builder(Arc::new(roots))
+                    .build()
+                    .unwrap(),
+            )
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let config = builder.with_single_cert(certificates, private_key).unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let listener = TcpListener::bind(address).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let Ok(stream) = acceptor.accept(socket).await else {
+                    return;
+                };
+                let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+                    return;
+                };
+                if let Some(Ok(Message::Text(text))) = ws.next().await {
+                    let ping: Value = serde_json::from_str(&text).unwrap();
+                    let pong = json!({"id": ping["id"], "type": "pong"});
+                    _ = ws.send(Message::Text(pong.to_string())).await;
+                }
+            });
+        }
+    });
+    port
+}
+
+async fn ping(port: u16, tls: TlsConfig) -> HassResult<String> {
+    ping_host("localhost", port, tls).await
+}
+
+async fn ping_host(host: &str, port: u16, tls: TlsConfig) -> HassResult<String> {
+    let url = url::Url::parse(&format!("wss://{}:{}/api/websocket", host, port)).unwrap();
+    let mut conn = HaClient::builder()
+        .tls(tls)
+        .build()
+        .connect_async(url)
+        .await?;
+    conn.ping().await
+}
+
+fn backends() -> Vec<TlsBackend> {
+    vec![
+        TlsBackend::Rustls,
+        #[cfg(feature = "native-tls")]
+        TlsBackend::NativeTls,
+    ]
+}
+
+#[tokio::test]
+async fn private_ca_should_be_trusted() {
+    let ca = private_ca();
+    let port = start_server(&ca.server, None).await;
+
+    for backend in backends() {
+        let tls = TlsConfig::new()
+            .backend(backend)
+            .root_certificate(&ca.ca_pem);
+        assert_eq!(ping(port, tls).await.unwrap(), "pong", "{:?}", backend);
+    }
+}
+
+#[tokio::test]
+async fn ipv6_address_should_match_certificate() {
+    let ca = private_ca();
+    let port = start_server_on("[::1]:0", &ca.server, None).await;
+
+    for backend in backends() {
+        let tls = TlsConfig::new()
+            .backend(backend)
+            .root_certificate(&ca.ca_pem);
+        assert_eq!(
+            ping_host("[::1]", port, tls).await.unwrap(),
+            "pong",
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[tokio::test]
+async fn unknown_ca_should_be_rejected() {
+    let ca = private_ca();
+    let port = start_server(&ca.server, None).await;
+
+    for backend in backends() {
+        let tls = TlsConfig::new().backend(backend);
+        assert!(ping(port, tls).await.is_err(), "{:?}", backend);
+    }
+}
+
+#[tokio::test]
+async fn pinned_certificate_should_be_accepted() {
+    let identity = self_signed();
+    let port = start_server(&identity, None).await;
+    let pin = fingerprint(&identity.certificate_pem);
+
+    for backend in backends() {
+        let tls = TlsConfig::new().backend(backend).pin_sha256(&pin).unwrap();
+        assert_eq!(ping(port, tls).await.unwrap(), "pong", "{:?}", backend);
+    }
+}
+
+#[tokio::test]
+async fn other_pinned_certificate_should_be_rejected() {
+    let port = start_server(&self_signed(), None).await;
+    let pin = fingerprint(&self_signed().certificate_pem);
+
+    for backend in backends() {
+        let tls = TlsConfig::new().backend(backend).pin_sha256(&pin).unwrap();
+        assert!(ping(port, tls).await.is_err(), "{:?}", backend);
+    }
+}
+
+#[tokio::test]
+async fn invalid_certificate_should_be_accepted_when_allowed() {
+    let port = start_server(&self_signed(), None).await;
+
+    for backend in backends() {
+        let tls = TlsConfig::new()
+            .backend(backend)
+            .danger_accept_invalid_certs(true);
+        assert_eq!(ping(port, tls).await.unwrap(), "pong", "{:?}", backend);
+    }
+}
+
+#[tokio::test]
+async fn client_certificate_should_be_sent() {
+    let ca = private_ca();
+    let port = start_server(&ca.server, Some(&ca.ca_pem)).await;
+
+    for backend in backends() {
+        let tls = TlsConfig::new()
+            .backend(backend)
+            .root_certificate(&ca.ca_pem);
+        assert!(ping(port, tls.clone()).await.is_err(), "{:?}", backend);
+
+        let tls = tls.client_identity(&ca.client.certificate_pem, &ca.client.private_key_pem);
+        assert_eq!(ping(port, tls).await.unwrap(), "pong", "{:?}", backend);
+    }
+}
+
+#[test]
+fn invalid_fingerprint_should_fail() {
+    assert!(TlsConfig::new().pin_sha256("AB:CD").is_err());
+    assert!(TlsConfig::new().pin_sha256(&"zz".repeat(32)).is_err());
+    // the last digit would be read as a byte of its own
+    assert!(TlsConfig::new().pin_sha256(&"A".repeat(63)).is_err());
+    // a sign is accepted when parsing numbers, it is no hex digit
+    assert!(TlsConfig::new().pin_sha256(&"+A".repeat(32)).is_err());
+    assert!(TlsConfig::new().pin_sha256(&"aB".repeat(32)).is_ok());
+}
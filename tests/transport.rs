@@ -0,0 +1,199 @@
+use futures_util::{SinkExt, StreamExt};
+use r_hassclient::{
+    transport::{
+        Direction, MemoryTransport, RecordedFrame, RecordingTransport, Replay, ReplaySpeed,
+    },
+    HaClient,
+};
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::Message;
+
+fn text(value: Value) -> Message {
+    Message::Text(value.to_string())
+}
+
+fn json(message: Option<Message>) -> Value {
+    match message {
+        Some(Message::Text(data)) => serde_json::from_str(&data).unwrap(),
+        x => panic!("We should have a text message! {:?}", x),
+    }
+}
+
+#[tokio::test]
+async fn client_should_run_over_memory_transport() {
+    let (transport, mut server) = MemoryTransport::pair();
+    let mut client = HaClient::builder().coalesce_messages(false).build();
+    let mut conn = client.connect_with(transport).await.unwrap();
+
+    server
+        .send(text(
+            json!({"type": "auth_required", "ha_version": "2024.1.0"}),
+        ))
+        .unwrap();
+    let server = tokio::spawn(async move {
+        let auth = json(server.recv().await);
+        assert_eq!(auth["access_token"], "token");
+        server
+            .send(text(json!({"type": "auth_ok", "ha_version": "2024.1.0"})))
+            .unwrap();
+
+        let ping = json(server.recv().await);
+        assert_eq!(ping["type"], "ping");
+        server
+            .send(text(json!({"id": ping["id"], "type": "pong"})))
+            .unwrap();
+    });
+
+    conn.authenticate_with_token("token").await.unwrap();
+    assert_eq!(conn.ping().await.unwrap(), "pong");
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn closed_transport_should_fail_commands() {
+    let (transport, server) = MemoryTransport::pair();
+    let mut conn = HaClient::builder()
+        .build()
+        .connect_with(transport)
+        .await
+        .unwrap();
+    drop(server);
+
+    assert!(conn.ping().await.is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn client_should_connect_over_unix_socket() {
+    let dir = std::env::temp_dir().join(format!("r-hassclient-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("ha.sock");
+    _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+    let server = tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+        let ping = json(ws.next().await.map(Result::unwrap));
+        ws.send(text(json!({"id": ping["id"], "type": "pong"})))
+            .await
+            .unwrap();
+    });
+
+    let url = url::Url::parse("ws://localhost/api/websocket").unwrap();
+    let mut conn = HaClient::builder()
+        .build()
+        .connect_unix_async(&path, url)
+        .await
+        .unwrap();
+    assert_eq!(conn.ping().await.unwrap(), "pong");
+
+    server.await.unwrap();
+    _ = std::fs::remove_dir_all(&dir);
+}
+
+fn recording_path(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("r-hassclient-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+async fn record_session(name: &str) -> std::path::PathBuf {
+    let path = recording_path(name);
+    let (transport, mut server) = MemoryTransport::pair();
+    let transport = RecordingTransport::new(transport, &path).unwrap();
+    let mut client = HaClient::builder().coalesce_messages(false).build();
+    let mut conn = client.connect_with(transport).await.unwrap();
+
+    server
+        .send(text(
+            json!({"type": "auth_required", "ha_version": "2024.1.0"}),
+        ))
+        .unwrap();
+    let server = tokio::spawn(async move {
+        json(server.recv().await);
+        server
+            .send(text(json!({"type": "auth_ok", "ha_version": "2024.1.0"})))
+            .unwrap();
+        let ping = json(server.recv().await);
+        server
+            .send(text(json!({"id": ping["id"], "type": "pong"})))
+            .unwrap();
+    });
+
+    conn.authenticate_with_token("secret-token").await.unwrap();
+    conn.ping().await.unwrap();
+    server.await.unwrap();
+    path
+}
+
+#[tokio::test]
+async fn recording_should_redact_the_access_token() {
+    let path = record_session("redacted.jsonl").await;
+
+    let recording = std::fs::read_to_string(&path).unwrap();
+    assert!(!recording.contains("secret-token"));
+    let frames: Vec<RecordedFrame> = recording
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let directions: Vec<Direction> = frames.iter().map(|frame| frame.direction).collect();
+    assert_eq!(
+        directions,
+        [
+            Direction::Received,
+            Direction::Sent,
+            Direction::Received,
+            Direction::Sent,
+            Direction::Received
+        ]
+    );
+}
+
+#[tokio::test]
+async fn replay_should_reproduce_recorded_session() {
+    let path = record_session("replayed.jsonl").await;
+
+    let (transport, replay) = Replay::load(&path)
+        .unwrap()
+        .speed(ReplaySpeed::Accelerated(100.0))
+        .start();
+    let mut client = HaClient::builder().coalesce_messages(false).build();
+    let mut conn = client.connect_with(transport).await.unwrap();
+
+    conn.authenticate_with_token("other-token").await.unwrap();
+    assert_eq!(conn.ping().await.unwrap(), "pong");
+    replay.finish().await.unwrap();
+}
+
+#[tokio::test]
+async fn replay_should_fail_when_commands_differ() {
+    let path = record_session("mismatch.jsonl").await;
+
+    let (transport, replay) = Replay::load(&path).unwrap().start();
+    let mut client = HaClient::builder().coalesce_messages(false).build();
+    let mut conn = client.connect_with(transport).await.unwrap();
+
+    conn.authenticate_with_token("token").await.unwrap();
+    // The recording expects a ping, the replay stops at the mismatch
+    _ = tokio::time::timeout(std::time::Duration::from_millis(100), conn.get_states()).await;
+    let error = replay.finish().await.unwrap_err();
+    assert!(error.to_string().contains("does not match the recording"));
+}
+
+#[tokio::test]
+async fn replay_should_fail_when_client_sends_after_recording() {
+    let path = record_session("exhausted.jsonl").await;
+
+    let (transport, replay) = Replay::load(&path).unwrap().start();
+    let mut client = HaClient::builder().coalesce_messages(false).build();
+    let mut conn = client.connect_with(transport).await.unwrap();
+
+    conn.authenticate_with_token("token").await.unwrap();
+    assert_eq!(conn.ping().await.unwrap(), "pong");
+    // The recording ends with the pong, the replay stops at the second ping
+    let extra = tokio::time::timeout(std::time::Duration::from_secs(1), conn.ping()).await;
+    assert!(matches!(extra, Ok(Err(_))));
+    let error = replay.finish().await.unwrap_err();
+    assert!(error.to_string().contains("after the end of the recording"));
+}